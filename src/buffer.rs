@@ -1,42 +1,93 @@
+use std::io::{self, Seek, SeekFrom, Read, Write};
+
+/// `ByteBuffer` reads and writes bytes at an absolute offset, and every formatter is built on it.
+/// It is implemented for `Seek + Read + Write` types such as `Cursor<Vec<u8>>`.
+/// `VecBuffer` implements it by indexing its bytes, without seeking.
+pub trait ByteBuffer {
+    /// Fill `buf` with the bytes at `offset`. It is an error if the buffer ends before `buf` is filled.
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+
+    /// Write all bytes of `buf` at `offset`.
+    fn write_at(&mut self, offset: u64, buf: &[u8]) -> io::Result<()>;
+
+    /// Return the number of bytes in the buffer.
+    fn byte_len(&mut self) -> io::Result<u64>;
+}
+
+impl<R> ByteBuffer for R where R: Seek + Read + Write {
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        try!(self.seek(SeekFrom::Start(offset)));
+        self.read_exact(buf)
+    }
+
+    fn write_at(&mut self, offset: u64, buf: &[u8]) -> io::Result<()> {
+        try!(self.seek(SeekFrom::Start(offset)));
+        self.write_all(buf)
+    }
+
+    fn byte_len(&mut self) -> io::Result<u64> {
+        self.seek(SeekFrom::End(0))
+    }
+}
+
+/// `MemoryBuffer` is a buffer over bytes in memory.
+/// `to_vec`, `from_slice` and object views create buffers through it.
+pub trait MemoryBuffer<'a>: ByteBuffer + Sized {
+    /// Create a buffer which writes into `bytes`.
+    fn from_vec(bytes: Vec<u8>) -> Self;
+
+    /// Create a buffer which reads `bytes` without copying them.
+    fn from_slice(bytes: &'a [u8]) -> Self;
+
+    fn into_vec(self) -> Vec<u8>;
+}
+
+/// `declare_buffer` define a newtype of `ByteBuffer`, which user types implement `Formatter` for.
+/// Because of orphan rules, `Formatter` cannot be implemented for buffer types of this crate directly.
 #[macro_export]
 macro_rules! declare_buffer {
     ($name:ident) => (
 
-        pub struct $name<T: Seek + ReadBytesExt + WriteBytesExt> {
+        pub struct $name<T: $crate::ByteBuffer> {
             pub inner: T
         }
 
         impl<T> $name<T>
-            where T: Seek + ReadBytesExt + WriteBytesExt {
+            where T: $crate::ByteBuffer {
 
-            pub fn new(inner: T) -> Buffer<T> { Buffer { inner: inner } }
+            pub fn new(inner: T) -> $name<T> { $name { inner: inner } }
         }
 
-        impl<T> Seek for $name<T>
-            where T: Seek + ReadBytesExt + WriteBytesExt {
+        impl<T> $crate::ByteBuffer for $name<T>
+            where T: $crate::ByteBuffer {
 
-            fn seek(&mut self, style: SeekFrom) -> Result<u64> {
-                self.inner.seek(style)
+            fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> ::std::io::Result<()> {
+                self.inner.read_at(offset, buf)
             }
-        }
 
-        impl<T> Read for $name<T>
-            where T: Seek + ReadBytesExt + WriteBytesExt {
+            fn write_at(&mut self, offset: u64, buf: &[u8]) -> ::std::io::Result<()> {
+                self.inner.write_at(offset, buf)
+            }
 
-            fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-                self.inner.read(buf)
+            fn byte_len(&mut self) -> ::std::io::Result<u64> {
+                self.inner.byte_len()
             }
         }
 
-        impl<T> Write for $name<T>
-            where T: Seek + ReadBytesExt + WriteBytesExt {
+        impl<'a, T> $crate::MemoryBuffer<'a> for $name<T>
+            where T: $crate::MemoryBuffer<'a> {
+
+            fn from_vec(bytes: Vec<u8>) -> $name<T> {
+                $name::new(T::from_vec(bytes))
+            }
 
-            fn write(&mut self, buf: &[u8]) -> Result<usize> {
-                self.inner.write(buf)
+            fn from_slice(bytes: &'a [u8]) -> $name<T> {
+                $name::new(T::from_slice(bytes))
             }
 
-            fn flush(&mut self) -> Result<()> {
-                self.inner.flush()
+            fn into_vec(self) -> Vec<u8> {
+                self.inner.into_vec()
             }
         }
     )
@@ -0,0 +1,484 @@
+//! Deserialize ZeroFormatter binary into a Rust data structure by serde.
+//!
+//! ZeroFormatter binary is not self-describing, so `deserialize_any` and `deserialize_ignored_any` are not supported.
+//! See also `ser` module for the format mapping.
+
+use error::*;
+use formatter::*;
+use util;
+use ser::{ObjectIndexes, NULLABLE, field_index};
+
+use std::fmt::Display;
+use std::borrow::Cow;
+use buffer::ByteBuffer;
+use serde::de::{self, Visitor, DeserializeSeed, DeserializeOwned, IntoDeserializer};
+
+/// `Deserializer` read values from `offset`.
+pub struct Deserializer<'a, R> {
+    reader: R,
+    offset: u64,
+    indexes: &'a ObjectIndexes,
+    nullable: bool
+}
+
+impl<'a, R> Deserializer<'a, R> where R: ByteBuffer {
+
+    pub fn new(reader: R, offset: u64, indexes: &'a ObjectIndexes) -> Deserializer<'a, R> {
+        Deserializer { reader, offset, indexes, nullable: false }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read<T>(&mut self) -> ZeroFormatterResult<T> where R: Formatter<T> {
+        self.reader.deserialize(&mut self.offset)
+    }
+}
+
+pub fn from_reader<R, T>(reader: R, offset: &mut u64) -> ZeroFormatterResult<T>
+    where R: ByteBuffer, T: DeserializeOwned {
+    from_reader_with_indexes(reader, offset, &ObjectIndexes::new())
+}
+
+pub fn from_reader_with_indexes<R, T>(reader: R, offset: &mut u64, indexes: &ObjectIndexes) -> ZeroFormatterResult<T>
+    where R: ByteBuffer, T: DeserializeOwned {
+    let mut deserializer = Deserializer::new(reader, *offset, indexes);
+    let value = try!(T::deserialize(&mut deserializer));
+    *offset = deserializer.offset;
+    Ok(value)
+}
+
+impl de::Error for ZeroFormatterError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ZeroFormatterError::Message(msg.to_string())
+    }
+}
+
+fn unsupported<T>(name: &str) -> ZeroFormatterResult<T> {
+    Err(ZeroFormatterError::Message(format!("{} is not supported", name)))
+}
+
+impl<'de, 'a, 'b, R> de::Deserializer<'de> for &'a mut Deserializer<'b, R> where R: ByteBuffer {
+    type Error = ZeroFormatterError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> ZeroFormatterResult<V::Value> {
+        unsupported("deserialize_any")
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_bool(try!(self.read()))
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_i8(try!(self.read()))
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_i16(try!(self.read()))
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_i32(try!(self.read()))
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_i64(try!(self.read()))
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_u8(try!(self.read()))
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_u16(try!(self.read()))
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_u32(try!(self.read()))
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_u64(try!(self.read()))
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_f32(try!(self.read()))
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_f64(try!(self.read()))
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_char(try!(self.read()))
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        let s: Cow<'static, str> = try!(self.read());
+        visitor.visit_string(s.into_owned())
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        let bytes: Cow<'static, [u8]> = try!(self.read());
+        visitor.visit_byte_buf(bytes.into_owned())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        if self.nullable {
+            self.nullable = false;
+            let mut offset = self.offset;
+            let len: i32 = try!(self.reader.deserialize(&mut offset));
+            if len == -1 {
+                self.offset = offset;
+                visitor.visit_none()
+            } else {
+                visitor.visit_some(self)
+            }
+        } else {
+            let has_value: bool = try!(self.read());
+            if has_value {
+                visitor.visit_some(self)
+            } else {
+                visitor.visit_none()
+            }
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> ZeroFormatterResult<V::Value> {
+        if name == NULLABLE {
+            self.nullable = true;
+        }
+        let r = visitor.visit_newtype_struct(&mut *self);
+        self.nullable = false;
+        r
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        let len = try!(util::check_non_null(&mut self.reader, &mut self.offset));
+        visitor.visit_seq(Elements { de: self, remaining: len })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> ZeroFormatterResult<V::Value> {
+        visitor.visit_seq(Elements { de: self, remaining: len as i32 })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _: &'static str, len: usize, visitor: V) -> ZeroFormatterResult<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        let len = try!(util::check_non_null(&mut self.reader, &mut self.offset));
+        visitor.visit_map(Elements { de: self, remaining: len })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> ZeroFormatterResult<V::Value> {
+        match self.indexes.get(name) {
+            None => self.deserialize_tuple(fields.len(), visitor),
+            Some(indexes) => {
+                let start_offset = self.offset;
+                let byte_size = try!(util::check_non_null(&mut self.reader, &mut self.offset));
+                let last_index: i32 = try!(self.read());
+
+                let mut present: Vec<(&'static str, u64)> = Vec::with_capacity(fields.len());
+                for field in fields {
+                    if let Some(index) = field_index(indexes, field) {
+                        if index <= last_index {
+                            let mut offset = start_offset + 4 + 4 + 4 * (index as u64);
+                            let o: i32 = try!(self.reader.deserialize(&mut offset));
+                            if o != 0 {
+                                present.push((field, o as u64));
+                            }
+                        }
+                    }
+                }
+
+                let value = try!(visitor.visit_map(Fields { de: &mut *self, fields: present.into_iter(), next: None }));
+                self.offset = start_offset + (byte_size as u64);
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _: &'static str, _: &'static [&'static str], visitor: V) -> ZeroFormatterResult<V::Value> {
+        let start_offset = self.offset;
+        let byte_size = try!(util::check_non_null(&mut self.reader, &mut self.offset));
+        let value = try!(visitor.visit_enum(&mut *self));
+        self.offset = start_offset + (byte_size as u64);
+        Ok(value)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> ZeroFormatterResult<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _: V) -> ZeroFormatterResult<V::Value> {
+        unsupported("deserialize_ignored_any")
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct Elements<'a, 'b: 'a, R: 'a> {
+    de: &'a mut Deserializer<'b, R>,
+    remaining: i32
+}
+
+impl<'de, 'a, 'b, R> de::SeqAccess<'de> for Elements<'a, 'b, R> where R: ByteBuffer {
+    type Error = ZeroFormatterError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> ZeroFormatterResult<Option<T::Value>> {
+        if self.remaining <= 0 {
+            Ok(None)
+        } else {
+            self.remaining -= 1;
+            seed.deserialize(&mut *self.de).map(Some)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining as usize)
+    }
+}
+
+impl<'de, 'a, 'b, R> de::MapAccess<'de> for Elements<'a, 'b, R> where R: ByteBuffer {
+    type Error = ZeroFormatterError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> ZeroFormatterResult<Option<K::Value>> {
+        if self.remaining <= 0 {
+            Ok(None)
+        } else {
+            self.remaining -= 1;
+            seed.deserialize(&mut *self.de).map(Some)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> ZeroFormatterResult<V::Value> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining as usize)
+    }
+}
+
+struct Fields<'a, 'b: 'a, R: 'a, I> {
+    de: &'a mut Deserializer<'b, R>,
+    fields: I,
+    next: Option<u64>
+}
+
+impl<'de, 'a, 'b, R, I> de::MapAccess<'de> for Fields<'a, 'b, R, I>
+    where R: ByteBuffer, I: Iterator<Item = (&'static str, u64)> {
+    type Error = ZeroFormatterError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> ZeroFormatterResult<Option<K::Value>> {
+        match self.fields.next() {
+            None => Ok(None),
+            Some((name, offset)) => {
+                self.next = Some(offset);
+                let key: de::value::StrDeserializer<ZeroFormatterError> = name.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> ZeroFormatterResult<V::Value> {
+        if let Some(offset) = self.next.take() {
+            self.de.offset = offset;
+        }
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+impl<'de, 'a, 'b, R> de::EnumAccess<'de> for &'a mut Deserializer<'b, R> where R: ByteBuffer {
+    type Error = ZeroFormatterError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> ZeroFormatterResult<(V::Value, Self)> {
+        let key: i32 = try!(self.read());
+        let key: de::value::U32Deserializer<ZeroFormatterError> = (key as u32).into_deserializer();
+        let v = try!(seed.deserialize(key));
+        Ok((v, self))
+    }
+}
+
+impl<'de, 'a, 'b, R> de::VariantAccess<'de> for &'a mut Deserializer<'b, R> where R: ByteBuffer {
+    type Error = ZeroFormatterError;
+
+    fn unit_variant(self) -> ZeroFormatterResult<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> ZeroFormatterResult<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> ZeroFormatterResult<V::Value> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> ZeroFormatterResult<V::Value> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use ser::ObjectIndexes;
+    use de::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct S {
+        a: i32,
+        b: i64
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct O {
+        a: i32,
+        b: i64
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct O2 {
+        a: i32,
+        b: i64,
+        #[serde(default)]
+        c: i8
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum U {
+        A(O),
+        B(S),
+        C
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct N {
+        #[serde(with = "::nullable")]
+        a: Option<String>,
+        b: Option<i32>
+    }
+
+    fn indexes() -> ObjectIndexes {
+        ObjectIndexes::new()
+            .object("O", &[(0, "a"), (1, "b")])
+            .object("O2", &[(0, "a"), (1, "b"), (2, "c")])
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!(S { a: 1, b: 2 }, from_reader(&mut rdr, &mut offset).unwrap());
+        assert_eq!(offset, 12);
+    }
+
+    #[test]
+    fn deserialize_object() {
+        let mut rdr = Cursor::new(vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!(O { a: 1, b: 2 }, from_reader_with_indexes(&mut rdr, &mut offset, &indexes()).unwrap());
+        assert_eq!(offset, 28);
+    }
+
+    #[test]
+    fn deserialize_object_versioning() {
+        let mut rdr = Cursor::new(vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!(O2 { a: 1, b: 2, c: 0 }, from_reader_with_indexes(&mut rdr, &mut offset, &indexes()).unwrap());
+        assert_eq!(offset, 28);
+    }
+
+    #[test]
+    fn deserialize_vec() {
+        let mut rdr = Cursor::new(vec![3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: Vec<i32> = from_reader(&mut rdr, &mut offset).unwrap();
+        assert_eq!(vec![1, 2, 3], actual);
+        assert_eq!(offset, 16);
+    }
+
+    #[test]
+    fn deserialize_char() {
+        let mut rdr = Cursor::new(vec![0x42, 0x30]);
+        let mut offset = 0;
+        let actual: char = from_reader(&mut rdr, &mut offset).unwrap();
+        assert_eq!('あ', actual);
+    }
+
+    #[test]
+    fn deserialize_str() {
+        let mut rdr = Cursor::new(vec![0x0f, 0, 0, 0, 0xe3, 0x81, 0x82, 0xe3, 0x81, 0x84, 0xe3, 0x81, 0x86, 0xe3, 0x81, 0x88, 0xe3, 0x81, 0x8a]);
+        let mut offset = 0;
+        let actual: String = from_reader(&mut rdr, &mut offset).unwrap();
+        assert_eq!("あいうえお", actual);
+    }
+
+    #[test]
+    fn deserialize_union() {
+        let mut rdr = Cursor::new(vec![
+            36, 0, 0, 0,
+            0, 0, 0, 0,
+            28, 0, 0, 0, 1, 0, 0, 0, 24, 0, 0, 0, 28, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0
+        ]);
+        let mut offset = 0;
+        assert_eq!(U::A(O { a: 1, b: 2 }), from_reader_with_indexes(&mut rdr, &mut offset, &indexes()).unwrap());
+        assert_eq!(offset, 36);
+    }
+
+    #[test]
+    fn deserialize_union_struct() {
+        let mut rdr = Cursor::new(vec![20, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!(U::B(S { a: 1, b: 2 }), from_reader(&mut rdr, &mut offset).unwrap());
+    }
+
+    #[test]
+    fn deserialize_union_unit() {
+        let mut rdr = Cursor::new(vec![8, 0, 0, 0, 2, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!(U::C, from_reader(&mut rdr, &mut offset).unwrap());
+    }
+
+    #[test]
+    fn deserialize_option() {
+        let mut rdr = Cursor::new(vec![0xff, 0xff, 0xff, 0xff, 1, 1, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!(N { a: None, b: Some(1) }, from_reader(&mut rdr, &mut offset).unwrap());
+        assert_eq!(offset, 9);
+    }
+
+    #[test]
+    fn deserialize_nullable_some() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0, 0x61, 0]);
+        let mut offset = 0;
+        assert_eq!(N { a: Some("a".to_string()), b: None }, from_reader(&mut rdr, &mut offset).unwrap());
+    }
+}
@@ -0,0 +1,105 @@
+use error::*;
+use formatter::*;
+
+use buffer::ByteBuffer;
+use rust_decimal::Decimal;
+
+const SIGN_MASK: u32 = 0x8000_0000;
+const SCALE_MASK: u32 = 0x00ff_0000;
+const SCALE_SHIFT: u32 = 16;
+const MAX_SCALE: u32 = 28;
+
+/// `Decimal` is the memory image of `System.Decimal`, `[flags:int][hi:int][lo:int][mid:int]`.
+/// `flags` has the scale in bits 16-23 and the sign in bit 31.
+impl<R> Formatter<Decimal> for R where R: ByteBuffer {
+
+    fn serialize(&mut self, offset: u64, value: Decimal) -> ZeroFormatterResult<i32> {
+        let m = value.mantissa().unsigned_abs();
+        let sign = if value.is_sign_negative() { SIGN_MASK } else { 0 };
+        let flags = sign | (value.scale() << SCALE_SHIFT);
+        let r1 = try!(self.serialize(offset, flags));
+        let r2 = try!(self.serialize(offset + 4, (m >> 64) as u32));
+        let r3 = try!(self.serialize(offset + 8, m as u32));
+        let r4 = try!(self.serialize(offset + 12, (m >> 32) as u32));
+        Ok(r1 + r2 + r3 + r4)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Decimal> {
+        let start_offset = *offset;
+        let flags: u32 = try!(self.deserialize(offset));
+        let hi: u32 = try!(self.deserialize(offset));
+        let lo: u32 = try!(self.deserialize(offset));
+        let mid: u32 = try!(self.deserialize(offset));
+        let scale = (flags & SCALE_MASK) >> SCALE_SHIFT;
+        if flags & !(SIGN_MASK | SCALE_MASK) != 0 || scale > MAX_SCALE {
+            return ZeroFormatterError::invalid(ErrorKind::InvalidDecimal(flags), start_offset);
+        }
+        Ok(Decimal::from_parts(lo, mid, hi, flags & SIGN_MASK != 0, scale))
+    }
+
+    fn length() -> Option<i32> {
+        Some(16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use std::str::FromStr;
+    use rust_decimal::Decimal;
+    use error::*;
+    use formatter::*;
+
+    #[test]
+    fn serialize_decimal() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, Decimal::from_str("-1.5").unwrap()).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![0, 0, 1, 0x80, 0, 0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn deserialize_decimal() {
+        let mut rdr = Cursor::new(vec![0, 0, 1, 0x80, 0, 0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: Decimal = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 16);
+        assert_eq!(Decimal::from_str("-1.5").unwrap(), actual);
+    }
+
+    #[test]
+    fn serialize_decimal_max() {
+        // decimal.MaxValue, 79228162514264337593543950335
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, Decimal::MAX).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn serialize_decimal_words() {
+        // new decimal(1, 2, 3, false, 4)
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, Decimal::from_parts(1, 2, 3, false, 4)).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![0, 0, 4, 0, 3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn deserialize_decimal_invalid_scale() {
+        let mut rdr = Cursor::new(vec![0, 0, 29, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<Decimal> = rdr.deserialize(&mut offset);
+        let e = actual.unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::InvalidDecimal(0x1d0000)));
+        assert_eq!(e.to_string(), "[offset 0] 0x001d0000 is not flags of a decimal");
+    }
+
+    #[test]
+    fn serialize_deserialize_decimal_some() {
+        let mut c = Cursor::new(Vec::new());
+        let input = Some(Decimal::from_str("123.456").unwrap());
+        assert_eq!(c.serialize(0, input).unwrap(), 17);
+        let mut offset = 0;
+        let actual: Option<Decimal> = c.deserialize(&mut offset).unwrap();
+        assert_eq!(input, actual);
+    }
+}
@@ -0,0 +1,569 @@
+use error::*;
+use formatter::*;
+use util;
+use list;
+use buffer::ByteBuffer;
+
+use std::hash::Hash;
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::collections::{HashMap, BTreeMap};
+
+macro_rules! dictionary_formatter_impl {
+    ($($t:ident; [$($bound:tt)*]),*) => ($(
+        impl<R, K, V> Formatter<$t<K, V>> for R
+            where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>, K: $($bound)* {
+
+            fn serialize(&mut self, offset: u64, value: $t<K, V>) -> ZeroFormatterResult<i32> {
+                let length = try!(util::length_to_i32(value.len(), offset));
+                let mut byte_size = try!(self.serialize(offset, length));
+                for (k, v) in value {
+                    byte_size += try!(self.serialize(offset + (byte_size as u64), k));
+                    byte_size += try!(self.serialize(offset + (byte_size as u64), v));
+                }
+                Ok(byte_size)
+            }
+
+            fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<$t<K, V>> {
+                let l: i32 = try!(util::check_non_null(self, offset));
+                let mut m = $t::new();
+                for _ in 0..l {
+                    let k: K = try!(self.deserialize(offset));
+                    let v: V = try!(self.deserialize(offset));
+                    m.insert(k, v);
+                }
+                Ok(m)
+            }
+
+            fn serialized_size(value: &$t<K, V>) -> ZeroFormatterResult<i32> {
+                let mut byte_size: i32 = 4;
+                for (k, v) in value {
+                    byte_size += try!(<R as Formatter<K>>::serialized_size(k));
+                    byte_size += try!(<R as Formatter<V>>::serialized_size(v));
+                }
+                Ok(byte_size)
+            }
+        }
+
+        impl<R, K, V> Formatter<Option<$t<K, V>>> for R
+            where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>, K: $($bound)* {
+
+            option_formatter_methods! { $t<K, V> }
+        }
+    )*)
+}
+
+dictionary_formatter_impl! {
+    HashMap; [Eq + Hash],
+    BTreeMap; [Ord]
+}
+
+/// `Lazy` selects the lazy format of the wrapped collection.
+///
+/// | Rust | C# |
+/// | ---- | ---- |
+/// | `Lazy<HashMap<K, V>>` | LazyDictionary |
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+pub struct Lazy<T>(pub T);
+
+/// `HashCode` provide hash code of dictionary keys which is written to LazyDictionary format.
+/// Integer keys follow `GetHashCode` of .NET.
+/// String keys follow `StringEqualityComparer` of ZeroFormatter, which is `String.GetHashCode` of 64bit .NET Framework.
+pub trait HashCode: Eq {
+    fn hash_code(&self) -> i32;
+}
+
+macro_rules! hash_code_impl {
+    ($($t:ty; $v:ident => $e:expr),*) => ($(
+        impl HashCode for $t {
+            fn hash_code(&self) -> i32 {
+                let $v = *self;
+                $e
+            }
+        }
+    )*)
+}
+
+hash_code_impl! {
+    u8; v => v as i32,
+    i8; v => (v as i32) ^ ((v as i32) << 8),
+    u16; v => v as i32,
+    i16; v => (v as u16 as i32) | ((v as i32) << 16),
+    u32; v => v as i32,
+    i32; v => v,
+    u64; v => (v as i32) ^ ((v >> 32) as i32),
+    i64; v => (v as i32) ^ ((v >> 32) as i32),
+    bool; v => if v { 1 } else { 0 }
+}
+
+/// Port of `String.GetHashCode` of 64bit .NET Framework.
+/// It reads UTF-16 code units two at a time, and stops at a null character like the original.
+fn string_hash_code(s: &str) -> i32 {
+    let mut hash1: i32 = 5381;
+    let mut hash2: i32 = hash1;
+    let mut units = s.encode_utf16();
+    loop {
+        match units.next() {
+            None | Some(0) => break,
+            Some(c) => hash1 = (hash1 << 5).wrapping_add(hash1) ^ (c as i32)
+        }
+        match units.next() {
+            None | Some(0) => break,
+            Some(c) => hash2 = (hash2 << 5).wrapping_add(hash2) ^ (c as i32)
+        }
+    }
+    hash1.wrapping_add(hash2.wrapping_mul(1566083941))
+}
+
+impl<'a> HashCode for Cow<'a, str> {
+    fn hash_code(&self) -> i32 {
+        string_hash_code(self)
+    }
+}
+
+impl HashCode for String {
+    fn hash_code(&self) -> i32 {
+        string_hash_code(self)
+    }
+}
+
+/// Return byte size of LazyDictionary format which has `length` entries.
+/// `keys_and_values` is the total byte size of the keys and the values.
+pub fn lazy_dictionary_serialized_size(length: i32, keys_and_values: i32) -> i32 {
+    let buckets = 4 + 4 * get_prime(length);
+    let entries = 4 + 4 + 4 * length + (4 + 4) * length + keys_and_values;
+    4 + 4 + buckets + entries
+}
+
+fn get_prime(min: i32) -> i32 {
+    let mut n = if min < 3 { 3 } else { min | 1 };
+    loop {
+        let mut i = 3;
+        let mut is_prime = true;
+        while i * i <= n {
+            if n % i == 0 {
+                is_prime = false;
+                break;
+            }
+            i += 2;
+        }
+        if is_prime {
+            return n;
+        }
+        n += 2;
+    }
+}
+
+/// `DictionaryEntry` is an entry of LazyDictionary format.
+/// `next` is the index of the next entry in the same bucket, or -1.
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+pub struct DictionaryEntry<K, V> {
+    pub hash_code: i32,
+    pub next: i32,
+    pub key: K,
+    pub value: V
+}
+
+impl<R, K, V> Formatter<DictionaryEntry<K, V>> for R
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32> {
+
+    fn serialize(&mut self, offset: u64, value: DictionaryEntry<K, V>) -> ZeroFormatterResult<i32> {
+        let mut byte_size = try!(self.serialize(offset, value.hash_code));
+        byte_size += try!(self.serialize(offset + (byte_size as u64), value.next));
+        byte_size += try!(self.serialize(offset + (byte_size as u64), value.key));
+        byte_size += try!(self.serialize(offset + (byte_size as u64), value.value));
+        Ok(byte_size)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<DictionaryEntry<K, V>> {
+        let hash_code: i32 = try!(self.deserialize(offset));
+        let next: i32 = try!(self.deserialize(offset));
+        let key: K = try!(self.deserialize(offset));
+        let value: V = try!(self.deserialize(offset));
+        Ok(DictionaryEntry { hash_code, next, key, value })
+    }
+
+    fn serialized_size(value: &DictionaryEntry<K, V>) -> ZeroFormatterResult<i32> {
+        let key = try!(<R as Formatter<K>>::serialized_size(&value.key));
+        let v = try!(<R as Formatter<V>>::serialized_size(&value.value));
+        Ok(4 + 4 + key + v)
+    }
+}
+
+impl<R, K, V> Formatter<Lazy<HashMap<K, V>>> for R
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>, K: Eq + Hash + HashCode {
+
+    fn serialize(&mut self, offset: u64, value: Lazy<HashMap<K, V>>) -> ZeroFormatterResult<i32> {
+        let length = try!(util::length_to_i32(value.0.len(), offset));
+        let mut buckets: Vec<i32> = vec![-1; get_prime(length) as usize];
+        let mut entries: Vec<DictionaryEntry<K, V>> = Vec::with_capacity(length as usize);
+        for (k, v) in value.0 {
+            let hash_code = k.hash_code() & 0x7FFFFFFF;
+            let bucket = (hash_code as usize) % buckets.len();
+            entries.push(DictionaryEntry { hash_code, next: buckets[bucket], key: k, value: v });
+            buckets[bucket] = (entries.len() - 1) as i32;
+        }
+
+        let mut byte_size: i32 = 4;
+        byte_size += try!(self.serialize(offset + (byte_size as u64), length));
+        byte_size += try!(self.serialize(offset + (byte_size as u64), buckets));
+        byte_size += try!(list::serialize_variable_size_list(self, offset + (byte_size as u64), entries));
+        try!(self.serialize(offset, byte_size));
+        Ok(byte_size)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Lazy<HashMap<K, V>>> {
+        let start_offset = *offset;
+        let byte_size = try!(util::check_non_null(self, offset));
+        let length = try!(util::check_non_null(self, offset));
+        let _: Vec<i32> = try!(self.deserialize(offset));
+        let entries: Vec<DictionaryEntry<K, V>> = try!(list::deserialize_variable_size_list(self, offset));
+        let mut m = HashMap::with_capacity(length as usize);
+        for e in entries {
+            m.insert(e.key, e.value);
+        }
+        *offset = start_offset + (byte_size as u64);
+        Ok(Lazy(m))
+    }
+
+    fn serialized_size(value: &Lazy<HashMap<K, V>>) -> ZeroFormatterResult<i32> {
+        let mut keys_and_values: i32 = 0;
+        for (k, v) in &value.0 {
+            keys_and_values += try!(<R as Formatter<K>>::serialized_size(k));
+            keys_and_values += try!(<R as Formatter<V>>::serialized_size(v));
+        }
+        Ok(lazy_dictionary_serialized_size(value.0.len() as i32, keys_and_values))
+    }
+}
+
+impl<R, K, V> Formatter<Option<Lazy<HashMap<K, V>>>> for R
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>, K: Eq + Hash + HashCode {
+
+    option_formatter_methods! { Lazy<HashMap<K, V>> }
+}
+
+/// `LazyDictionary` is a lazy view of LazyDictionary format.
+/// It looks up a key through the bucket table, and reads only the matching value.
+///
+/// ```
+/// # extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::Cursor;
+/// # use std::collections::HashMap;
+/// #
+/// # fn main() {
+/// let mut map = HashMap::new();
+/// map.insert(1i32, 10i64);
+/// map.insert(2i32, 20i64);
+/// let mut buffer = Cursor::new(Vec::new());
+/// buffer.serialize(0, Lazy(map)).unwrap();
+///
+/// let mut offset = 0;
+/// let mut dictionary: LazyDictionary<_, i32, i64> = LazyDictionary::new(&mut buffer, &mut offset).unwrap();
+/// assert_eq!(dictionary.len(), 2);
+/// assert_eq!(dictionary.get(&2).unwrap(), Some(20));
+/// assert_eq!(dictionary.get(&3).unwrap(), None);
+/// # }
+/// ```
+pub struct LazyDictionary<'a, R: 'a, K, V> {
+    buffer: &'a mut R,
+    length: i32,
+    buckets_offset: u64,
+    bucket_count: i32,
+    entries_offset: u64,
+    _entry: PhantomData<(K, V)>
+}
+
+impl<'a, R, K, V> LazyDictionary<'a, R, K, V>
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>, K: HashCode {
+
+    /// Read the header at `offset`, and move `offset` to the end of the dictionary.
+    pub fn new(buffer: &'a mut R, offset: &mut u64) -> ZeroFormatterResult<LazyDictionary<'a, R, K, V>> {
+        let start_offset = *offset;
+        let byte_size = try!(util::check_non_null(buffer, offset));
+        let length = try!(util::check_non_null(buffer, offset));
+        let bucket_count = try!(util::check_non_null(buffer, offset));
+        let buckets_offset = *offset;
+        let entries_offset = buckets_offset + 4 * (bucket_count as u64);
+        *offset = start_offset + (byte_size as u64);
+        Ok(LazyDictionary {
+            buffer,
+            length,
+            buckets_offset,
+            bucket_count,
+            entries_offset,
+            _entry: PhantomData
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Find `key` and return the offset of its value.
+    /// The chain of entries is followed at most `length` times.
+    fn find(&mut self, key: &K) -> ZeroFormatterResult<Option<u64>> {
+        if self.bucket_count <= 0 {
+            return Ok(None);
+        }
+        let hash_code = key.hash_code() & 0x7FFFFFFF;
+        let mut slot = self.buckets_offset + 4 * ((hash_code % self.bucket_count) as u64);
+        let mut o = slot;
+        let mut i: i32 = try!(self.buffer.deserialize(&mut o));
+        let mut steps = 0;
+        while i >= 0 {
+            if i >= self.length {
+                return ZeroFormatterError::invalid(ErrorKind::OffsetOutOfRange(i), slot);
+            }
+            if steps == self.length {
+                return ZeroFormatterError::invalid(ErrorKind::CyclicChain, slot);
+            }
+            steps += 1;
+            let mut o = self.entries_offset + 4 + 4 + 4 * (i as u64);
+            let mut entry_offset = try!(util::check_non_null(self.buffer, &mut o)) as u64;
+            let h: i32 = try!(self.buffer.deserialize(&mut entry_offset));
+            slot = entry_offset;
+            let next: i32 = try!(self.buffer.deserialize(&mut entry_offset));
+            if h == hash_code {
+                let k: K = try!(self.buffer.deserialize(&mut entry_offset));
+                if k == *key {
+                    return Ok(Some(entry_offset));
+                }
+            }
+            i = next;
+        }
+        Ok(None)
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> ZeroFormatterResult<bool> {
+        self.find(key).map(|o| o.is_some())
+    }
+
+    /// Read the value of `key`. Return `None` if `key` is not found.
+    pub fn get(&mut self, key: &K) -> ZeroFormatterResult<Option<V>> {
+        match try!(self.find(key)) {
+            None => Ok(None),
+            Some(mut o) => self.buffer.deserialize(&mut o).map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use std::borrow::Cow;
+    use std::collections::{HashMap, BTreeMap};
+    use formatter::*;
+    use dictionary::*;
+
+    #[test]
+    fn serialize_hash_map() {
+        let mut wtr = Cursor::new(Vec::new());
+        let mut input = HashMap::new();
+        input.insert(1i32, 2i16);
+        assert_eq!(wtr.serialize(0, input).unwrap(), 10);
+        assert_eq!(wtr.into_inner(), vec![1, 0, 0, 0, 1, 0, 0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn deserialize_hash_map() {
+        let mut rdr = Cursor::new(vec![2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 3, 0, 0, 0, 4, 0]);
+        let mut offset = 0;
+        let actual: HashMap<i32, i16> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 16);
+        let mut expected = HashMap::new();
+        expected.insert(1, 2);
+        expected.insert(3, 4);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn serialize_btree_map() {
+        let mut wtr = Cursor::new(Vec::new());
+        let mut input = BTreeMap::new();
+        input.insert(Cow::Borrowed("b"), 2u8);
+        input.insert(Cow::Borrowed("a"), 1u8);
+        assert_eq!(wtr.serialize(0, input).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![2, 0, 0, 0, 1, 0, 0, 0, 0x61, 1, 1, 0, 0, 0, 0x62, 2]);
+    }
+
+    #[test]
+    fn deserialize_btree_map() {
+        let mut rdr = Cursor::new(vec![2, 0, 0, 0, 1, 0, 0, 0, 0x61, 1, 1, 0, 0, 0, 0x62, 2]);
+        let mut offset = 0;
+        let actual: BTreeMap<Cow<'static, str>, u8> = rdr.deserialize(&mut offset).unwrap();
+        let mut expected = BTreeMap::new();
+        expected.insert(Cow::Borrowed("a"), 1u8);
+        expected.insert(Cow::Borrowed("b"), 2u8);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn serialize_hash_map_none() {
+        let mut wtr = Cursor::new(Vec::new());
+        let input: Option<HashMap<i32, i32>> = None;
+        assert_eq!(wtr.serialize(0, input).unwrap(), 4);
+        assert_eq!(wtr.into_inner(), vec![0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn deserialize_hash_map_none() {
+        let mut rdr = Cursor::new(vec![0xff, 0xff, 0xff, 0xff]);
+        let mut offset = 0;
+        let expected: Option<HashMap<i32, i32>> = None;
+        assert_eq!(expected, rdr.deserialize(&mut offset).unwrap());
+    }
+
+    #[test]
+    fn deserialize_btree_map_some() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0, 1, 0, 0, 0, 2, 0]);
+        let mut offset = 0;
+        let actual: Option<BTreeMap<i32, i16>> = rdr.deserialize(&mut offset).unwrap();
+        let mut expected = BTreeMap::new();
+        expected.insert(1, 2);
+        assert_eq!(Some(expected), actual);
+    }
+
+    #[test]
+    fn serialize_lazy_dictionary() {
+        let mut wtr = Cursor::new(Vec::new());
+        let mut input = HashMap::new();
+        input.insert(1i32, 2i16);
+        assert_eq!(wtr.serialize(0, Lazy(input)).unwrap(), 50);
+        assert_eq!(wtr.into_inner(), vec![
+            50, 0, 0, 0,
+            1, 0, 0, 0,
+            3, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff,
+            26, 0, 0, 0,
+            1, 0, 0, 0,
+            36, 0, 0, 0,
+            1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 1, 0, 0, 0, 2, 0
+        ]);
+    }
+
+    #[test]
+    fn serialize_deserialize_lazy_dictionary() {
+        let mut c = Cursor::new(Vec::new());
+        let mut input = HashMap::new();
+        for i in 0..10i64 {
+            input.insert(i, i * 10);
+        }
+        c.serialize(0, Lazy(input.clone())).unwrap();
+        let mut offset = 0;
+        let actual: Lazy<HashMap<i64, i64>> = c.deserialize(&mut offset).unwrap();
+        assert_eq!(Lazy(input), actual);
+    }
+
+    #[test]
+    fn lazy_dictionary_get() {
+        let mut c = Cursor::new(Vec::new());
+        let mut input = HashMap::new();
+        for i in 0..100i32 {
+            input.insert(Cow::Owned(i.to_string()), i);
+        }
+        c.serialize(0, Lazy(input)).unwrap();
+        let mut offset = 0;
+        let mut dictionary: LazyDictionary<_, Cow<'static, str>, i32> = LazyDictionary::new(&mut c, &mut offset).unwrap();
+        assert_eq!(dictionary.len(), 100);
+        for i in 0..100i32 {
+            assert_eq!(dictionary.get(&Cow::Owned(i.to_string())).unwrap(), Some(i));
+        }
+        assert_eq!(dictionary.get(&Cow::Borrowed("100")).unwrap(), None);
+        assert!(dictionary.contains_key(&Cow::Borrowed("42")).unwrap());
+    }
+
+    #[test]
+    fn lazy_dictionary_get_colliding_keys() {
+        let mut c = Cursor::new(Vec::new());
+        let mut input = HashMap::new();
+        input.insert(0i32, 1u8);
+        input.insert(3i32, 2u8);
+        input.insert(6i32, 3u8);
+        c.serialize(0, Lazy(input)).unwrap();
+        let mut offset = 0;
+        let mut dictionary: LazyDictionary<_, i32, u8> = LazyDictionary::new(&mut c, &mut offset).unwrap();
+        assert_eq!(dictionary.get(&0).unwrap(), Some(1));
+        assert_eq!(dictionary.get(&3).unwrap(), Some(2));
+        assert_eq!(dictionary.get(&6).unwrap(), Some(3));
+        assert_eq!(dictionary.get(&9).unwrap(), None);
+    }
+
+    fn single_entry_lazy_dictionary() -> Cursor<Vec<u8>> {
+        let mut c = Cursor::new(Vec::new());
+        let mut input = HashMap::new();
+        input.insert(1i32, 2i16);
+        c.serialize(0, Lazy(input)).unwrap();
+        c
+    }
+
+    #[test]
+    fn lazy_dictionary_get_cyclic_chain() {
+        let mut c = single_entry_lazy_dictionary();
+        // next of entry 0 points to entry 0
+        c.get_mut()[40..44].copy_from_slice(&[0, 0, 0, 0]);
+        let mut offset = 0;
+        let mut dictionary: LazyDictionary<_, i32, i16> = LazyDictionary::new(&mut c, &mut offset).unwrap();
+        assert_eq!(dictionary.get(&1).unwrap(), Some(2));
+        let e = dictionary.get(&4).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::CyclicChain));
+        assert_eq!(e.offset(), Some(40));
+    }
+
+    #[test]
+    fn lazy_dictionary_get_entry_out_of_range() {
+        let mut c = single_entry_lazy_dictionary();
+        c.get_mut()[16..20].copy_from_slice(&[1, 0, 0, 0]);
+        let mut offset = 0;
+        let mut dictionary: LazyDictionary<_, i32, i16> = LazyDictionary::new(&mut c, &mut offset).unwrap();
+        let e = dictionary.get(&1).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::OffsetOutOfRange(1)));
+        assert_eq!(e.offset(), Some(16));
+    }
+
+    #[test]
+    fn hash_code() {
+        assert_eq!((-1i64).hash_code(), 0);
+        assert_eq!((1i64 << 32).hash_code(), 1);
+        assert_eq!((-1i16).hash_code(), -1);
+        assert_eq!(2i16.hash_code(), 0x20002);
+        assert_eq!(true.hash_code(), 1);
+    }
+
+    #[test]
+    fn hash_code_string() {
+        assert_eq!(Cow::Borrowed("").hash_code(), 371857150);
+        assert_eq!(Cow::Borrowed("a").hash_code(), 372029373);
+        assert_eq!(Cow::Borrowed("Hello").hash_code(), -327378614);
+        assert_eq!(String::from("hello world").hash_code(), 1118511802);
+        assert_eq!(String::from("\u{3042}\u{3044}\u{3046}").hash_code(), 576417606);
+        assert_eq!(String::from("\u{1F600}").hash_code(), 1126268081);
+        assert_eq!(String::from("a\u{0}b").hash_code(), Cow::Borrowed("a").hash_code());
+    }
+
+    #[test]
+    fn serialized_size_dictionary() {
+        let mut input = HashMap::new();
+        input.insert(1i32, Cow::Borrowed("a"));
+        input.insert(2i32, Cow::Borrowed("bc"));
+        let mut wtr = Cursor::new(Vec::new());
+        let expected = wtr.serialize(0, input.clone()).unwrap();
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<HashMap<i32, Cow<str>>>>::serialized_size(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn serialized_size_lazy_dictionary() {
+        let mut m = HashMap::new();
+        for i in 0..10 {
+            m.insert(i, Cow::Owned(i.to_string()));
+        }
+        let input = Lazy(m);
+        let mut wtr = Cursor::new(Vec::new());
+        let expected = wtr.serialize(0, input.clone()).unwrap();
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Lazy<HashMap<i32, Cow<str>>>>>::serialized_size(&input).unwrap(), expected);
+    }
+}
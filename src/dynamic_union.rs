@@ -0,0 +1,227 @@
+use error::*;
+use formatter::*;
+use util;
+
+use std::any::{Any, TypeId};
+use std::hash::Hash;
+use std::collections::HashMap;
+use buffer::ByteBuffer;
+
+struct DynamicCase<R, K> {
+    key: K,
+    serialize: fn(&mut R, u64, Box<dyn Any>) -> ZeroFormatterResult<i32>,
+    deserialize: fn(&mut R, &mut u64) -> ZeroFormatterResult<Box<dyn Any>>,
+    serialized_size: fn(&dyn Any) -> ZeroFormatterResult<i32>
+}
+
+fn serialize_case<R, T>(r: &mut R, offset: u64, value: Box<dyn Any>) -> ZeroFormatterResult<i32>
+    where R: ByteBuffer + Formatter<T>, T: Any {
+    match value.downcast::<T>() {
+        Ok(v) => r.serialize(offset, *v),
+        Err(_) => Err(ZeroFormatterError::InvalidValue(ErrorKind::TypeMismatch))
+    }
+}
+
+fn deserialize_case<R, T>(r: &mut R, offset: &mut u64) -> ZeroFormatterResult<Box<dyn Any>>
+    where R: ByteBuffer + Formatter<T>, T: Any {
+    let v: T = try!(r.deserialize(offset));
+    Ok(Box::new(v))
+}
+
+fn serialized_size_case<R, T>(value: &dyn Any) -> ZeroFormatterResult<i32>
+    where R: ByteBuffer + Formatter<T>, T: Any {
+    match value.downcast_ref::<T>() {
+        Some(v) => <R as Formatter<T>>::serialized_size(v),
+        None => Err(ZeroFormatterError::InvalidValue(ErrorKind::TypeMismatch))
+    }
+}
+
+/// `DynamicUnion` is a registry of union cases which are added at runtime, like `DynamicUnion` of ZeroFormatter.
+/// The layout is the same as Union format, `[byteSize:int][unionKey:K][value:T]`.
+/// Values are passed and returned as `Box<dyn Any>`, and each type is registered to one key.
+///
+/// ```
+/// # extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::Cursor;
+/// # use std::borrow::Cow;
+/// #
+/// # fn main() {
+/// let mut union: DynamicUnion<Cursor<Vec<u8>>, i32> = DynamicUnion::new();
+/// union.register::<i64>(1).unwrap();
+/// union.register::<Cow<'static, str>>(2).unwrap();
+///
+/// let mut buffer = Cursor::new(Vec::new());
+/// union.serialize(&mut buffer, 0, Box::new(Cow::Borrowed("a") as Cow<'static, str>)).unwrap();
+/// let mut offset = 0;
+/// let actual = union.deserialize(&mut buffer, &mut offset).unwrap();
+/// assert_eq!(actual.downcast_ref::<Cow<'static, str>>(), Some(&Cow::Borrowed("a")));
+/// # }
+/// ```
+pub struct DynamicUnion<R, K> {
+    cases: Vec<DynamicCase<R, K>>,
+    keys: HashMap<K, usize>,
+    types: HashMap<TypeId, usize>
+}
+
+impl<R, K> DynamicUnion<R, K>
+    where R: ByteBuffer + Formatter<K> + Formatter<i32>, K: Eq + Hash + Clone {
+
+    pub fn new() -> DynamicUnion<R, K> {
+        DynamicUnion { cases: Vec::new(), keys: HashMap::new(), types: HashMap::new() }
+    }
+
+    /// Register `T` as the case of `key`.
+    /// It is an error if `key` or `T` is already registered.
+    pub fn register<T: Any>(&mut self, key: K) -> ZeroFormatterResult<()> where R: Formatter<T> {
+        let type_id = TypeId::of::<T>();
+        if self.keys.contains_key(&key) {
+            return Err(ZeroFormatterError::InvalidValue(ErrorKind::DuplicateKey));
+        }
+        if self.types.contains_key(&type_id) {
+            return Err(ZeroFormatterError::InvalidValue(ErrorKind::DuplicateType));
+        }
+        let index = self.cases.len();
+        self.cases.push(DynamicCase {
+            key: key.clone(),
+            serialize: serialize_case::<R, T>,
+            deserialize: deserialize_case::<R, T>,
+            serialized_size: serialized_size_case::<R, T>
+        });
+        self.keys.insert(key, index);
+        self.types.insert(type_id, index);
+        Ok(())
+    }
+
+    /// Return the key which the type of `value` is registered to.
+    pub fn key_of(&self, value: &dyn Any) -> Option<&K> {
+        self.types.get(&value.type_id()).map(|&i| &self.cases[i].key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cases.is_empty()
+    }
+
+    fn case_of(&self, value: &dyn Any) -> ZeroFormatterResult<&DynamicCase<R, K>> {
+        match self.types.get(&value.type_id()) {
+            Some(&i) => Ok(&self.cases[i]),
+            None => Err(ZeroFormatterError::InvalidValue(ErrorKind::UnregisteredType))
+        }
+    }
+
+    pub fn serialize(&self, buffer: &mut R, offset: u64, value: Box<dyn Any>) -> ZeroFormatterResult<i32> {
+        let case = try!(self.case_of(&*value));
+        let mut byte_size: i32 = 4;
+        byte_size += try!(buffer.serialize(offset + (byte_size as u64), case.key.clone()));
+        byte_size += try!((case.serialize)(buffer, offset + (byte_size as u64), value));
+        try!(buffer.serialize(offset, byte_size));
+        Ok(byte_size)
+    }
+
+    /// Deserialize the value of the registered case.
+    /// An unknown key is an error.
+    pub fn deserialize(&self, buffer: &mut R, offset: &mut u64) -> ZeroFormatterResult<Box<dyn Any>> {
+        let start_offset = *offset;
+        let byte_size = try!(util::check_non_null(buffer, offset));
+        let key: K = try!(buffer.deserialize(offset));
+        let value = match self.keys.get(&key) {
+            Some(&i) => try!((self.cases[i].deserialize)(buffer, offset)),
+            None => return ZeroFormatterError::invalid(ErrorKind::UnknownUnionKey, start_offset + 4)
+        };
+        *offset = start_offset + (byte_size as u64);
+        Ok(value)
+    }
+
+    pub fn serialized_size(&self, value: &dyn Any) -> ZeroFormatterResult<i32> {
+        let case = try!(self.case_of(value));
+        let key = try!(<R as Formatter<K>>::serialized_size(&case.key));
+        let v = try!((case.serialized_size)(value));
+        Ok(4 + key + v)
+    }
+}
+
+impl<R, K> Default for DynamicUnion<R, K>
+    where R: ByteBuffer + Formatter<K> + Formatter<i32>, K: Eq + Hash + Clone {
+
+    fn default() -> DynamicUnion<R, K> {
+        DynamicUnion::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use std::any::Any;
+    use std::borrow::Cow;
+    use error::*;
+    use dynamic_union::*;
+
+    fn registry() -> DynamicUnion<Cursor<Vec<u8>>, i32> {
+        let mut union = DynamicUnion::new();
+        union.register::<i64>(1).unwrap();
+        union.register::<Cow<'static, str>>(2).unwrap();
+        union
+    }
+
+    #[test]
+    fn serialize_dynamic_union() {
+        let union = registry();
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(union.serialize(&mut wtr, 0, Box::new(3i64)).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![16, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn deserialize_dynamic_union() {
+        let union = registry();
+        let mut rdr = Cursor::new(vec![13, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0x61]);
+        let mut offset = 0;
+        let actual = union.deserialize(&mut rdr, &mut offset).unwrap();
+        assert_eq!(actual.downcast_ref::<Cow<'static, str>>(), Some(&Cow::Borrowed("a")));
+        assert_eq!(offset, 13);
+    }
+
+    #[test]
+    fn deserialize_dynamic_union_unknown_key() {
+        let union = registry();
+        let mut rdr = Cursor::new(vec![16, 0, 0, 0, 3, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        let e = union.deserialize(&mut rdr, &mut offset).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::UnknownUnionKey));
+        assert_eq!(e.offset(), Some(4));
+    }
+
+    #[test]
+    fn register_duplicate() {
+        let mut union = registry();
+        assert_eq!(union.register::<i32>(1).unwrap_err().kind(), Some(ErrorKind::DuplicateKey));
+        let e = union.register::<i64>(3).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::DuplicateType));
+        assert_eq!(e.offset(), None);
+        assert_eq!(e.to_string(), "the type is already registered");
+        assert_eq!(union.len(), 2);
+    }
+
+    #[test]
+    fn serialize_unregistered_type() {
+        let union = registry();
+        let mut wtr = Cursor::new(Vec::new());
+        let actual: ZeroFormatterResult<i32> = union.serialize(&mut wtr, 0, Box::new(1u8));
+        assert_eq!(actual.unwrap_err().kind(), Some(ErrorKind::UnregisteredType));
+        assert_eq!(union.serialized_size(&1u8).unwrap_err().kind(), Some(ErrorKind::UnregisteredType));
+        assert_eq!(union.key_of(&1u8 as &dyn Any), None);
+        assert_eq!(union.key_of(&1i64 as &dyn Any), Some(&1));
+    }
+
+    #[test]
+    fn serialized_size_dynamic_union() {
+        let union = registry();
+        let input: Cow<'static, str> = Cow::Borrowed("abc");
+        assert_eq!(union.serialized_size(&input).unwrap(), 15);
+    }
+}
@@ -0,0 +1,295 @@
+/// `enum_formatter` define C-like enum type and provide formatter which writes the underlying integer, like enum of C#.
+/// Deserializing an unknown value is an error.
+///
+/// ```
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::{Cursor, Result};
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// enum_formatter! {
+///     #[target(Buffer<Cursor<Vec<u8>>>)]
+///     enum Color: u8 {
+///         Red = 1,
+///         Green = 2,
+///         Blue = 3
+///     }
+/// }
+///
+/// # fn example() -> Result<()> {
+/// # let mut writer = Buffer::new(Cursor::new(Vec::new()));
+/// try!(writer.serialize(0, Color::Red));
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// # example();
+/// # }
+/// ```
+///
+/// `#[flags]` defines a newtype struct instead of enum for `[Flags]` enum of C#.
+/// It accepts any combination of the values.
+///
+/// ```
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::{Cursor, Result};
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// enum_formatter! {
+///     #[target(Buffer<Cursor<Vec<u8>>>)]
+///     #[flags]
+///     struct Permission: i32 {
+///         READ = 1,
+///         WRITE = 2,
+///         EXECUTE = 4
+///     }
+/// }
+///
+/// # fn example() -> Result<()> {
+/// # let mut writer = Buffer::new(Cursor::new(Vec::new()));
+/// let p = Permission::READ | Permission::WRITE;
+/// assert!(p.contains(Permission::WRITE));
+/// try!(writer.serialize(0, p));
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// # example();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! enum_formatter {
+    (#[target($buffer:ty)]
+    enum $name:ident : $repr:ident {
+        $($case_name:ident = $value:expr),*
+    }) => {
+        #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+        #[repr($repr)]
+        pub enum $name {
+            $($case_name = $value),*
+        }
+
+        impl $crate::UnionKey for $name {
+            type Literal = $name;
+            type Pattern = $name;
+
+            fn from_literal(literal: $name) -> $name {
+                literal
+            }
+
+            fn as_pattern(&self) -> &$name {
+                self
+            }
+        }
+
+        impl Formatter<$name> for $buffer {
+
+            fn serialize(&mut self, offset: u64, value: $name) -> ZeroFormatterResult<i32> {
+                self.serialize(offset, value as $repr)
+            }
+
+            fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<$name> {
+                let start_offset = *offset;
+                let n: $repr = try!(self.deserialize(offset));
+                $(
+                if n == $name::$case_name as $repr {
+                    return Ok($name::$case_name);
+                }
+                )*
+                ZeroFormatterError::invalid($crate::ErrorKind::UnknownEnumValue(stringify!($name), n as i128), start_offset)
+            }
+
+            fn length() -> Option<i32> {
+                <Self as Formatter<$repr>>::length()
+            }
+        }
+
+        has_value_formatter! {
+            #[target($buffer)]
+            $name
+        }
+    };
+    (#[target($buffer:ty)]
+    #[flags]
+    struct $name:ident : $repr:ident {
+        $($flag_name:ident = $value:expr),*
+    }) => {
+        #[derive(Default, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+        pub struct $name(pub $repr);
+
+        #[allow(dead_code)]
+        impl $name {
+            $(pub const $flag_name: $name = $name($value);)*
+
+            pub fn contains(&self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+        }
+
+        impl ::std::ops::BitOr for $name {
+            type Output = $name;
+
+            fn bitor(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl ::std::ops::BitAnd for $name {
+            type Output = $name;
+
+            fn bitand(self, other: $name) -> $name {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl Formatter<$name> for $buffer {
+
+            fn serialize(&mut self, offset: u64, value: $name) -> ZeroFormatterResult<i32> {
+                self.serialize(offset, value.0)
+            }
+
+            fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<$name> {
+                self.deserialize(offset).map($name)
+            }
+
+            fn length() -> Option<i32> {
+                <Self as Formatter<$repr>>::length()
+            }
+        }
+
+        has_value_formatter! {
+            #[target($buffer)]
+            $name
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use error::*;
+    use formatter::*;
+    use util;
+
+    enum_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        enum E: i16 {
+            A = 1,
+            B = -2
+        }
+    }
+
+    enum_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        enum L: u64 {
+            A = 1,
+            B = 0xffff_ffff_ffff_fffe
+        }
+    }
+
+    enum_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        #[flags]
+        struct F: u8 {
+            X = 1,
+            Y = 2,
+            Z = 4
+        }
+    }
+
+    #[test]
+    fn serialize_enum() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, E::B).unwrap(), 2);
+        assert_eq!(wtr.into_inner(), vec![0xfe, 0xff]);
+    }
+
+    #[test]
+    fn deserialize_enum() {
+        let mut rdr = Cursor::new(vec![1, 0]);
+        let mut offset = 0;
+        assert_eq!(E::A, rdr.deserialize(&mut offset).unwrap());
+        assert_eq!(offset, 2);
+    }
+
+    #[test]
+    fn deserialize_enum_unknown() {
+        let mut rdr = Cursor::new(vec![3, 0]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<E> = rdr.deserialize(&mut offset);
+        let e = actual.unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::UnknownEnumValue("E", 3)));
+        assert_eq!(e.offset(), Some(0));
+        assert_eq!(e.to_string(), "[offset 0] 3 is not a value of E");
+    }
+
+    #[test]
+    fn deserialize_enum_unknown_u64() {
+        let mut rdr = Cursor::new(vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<L> = rdr.deserialize(&mut offset);
+        let e = actual.unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::UnknownEnumValue("L", 0xffff_ffff_ffff_ffff)));
+        assert_eq!(e.to_string(), "[offset 0] 18446744073709551615 is not a value of L");
+        let mut rdr = Cursor::new(vec![0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        let mut offset = 0;
+        assert_eq!(L::B, rdr.deserialize(&mut offset).unwrap());
+    }
+
+    #[test]
+    fn serialize_enum_some() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, Some(E::A)).unwrap(), 3);
+        assert_eq!(wtr.into_inner(), vec![1, 1, 0]);
+    }
+
+    #[test]
+    fn deserialize_enum_none() {
+        let mut rdr = Cursor::new(vec![0]);
+        let mut offset = 0;
+        let expected: Option<E> = None;
+        assert_eq!(expected, rdr.deserialize(&mut offset).unwrap());
+    }
+
+    #[test]
+    fn serialize_flags() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, F::X | F::Z).unwrap(), 1);
+        assert_eq!(wtr.into_inner(), vec![5]);
+    }
+
+    #[test]
+    fn deserialize_flags() {
+        let mut rdr = Cursor::new(vec![3]);
+        let mut offset = 0;
+        let actual: F = rdr.deserialize(&mut offset).unwrap();
+        assert!(actual.contains(F::X));
+        assert!(actual.contains(F::Y));
+        assert!(!actual.contains(F::Z));
+        assert_eq!(actual & F::Y, F::Y);
+    }
+
+    union_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        enum U: E {
+            E::A; A(i32),
+            E::B; B
+        }
+    }
+
+    #[test]
+    fn serialize_deserialize_union_enum_key() {
+        let mut c = Cursor::new(Vec::new());
+        assert_eq!(c.serialize(0, U::A(3)).unwrap(), 10);
+        assert_eq!(c.get_ref(), &vec![10, 0, 0, 0, 1, 0, 3, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!(U::A(3), c.deserialize(&mut offset).unwrap());
+        c.serialize(0, U::B).unwrap();
+        let mut offset = 0;
+        assert_eq!(U::B, c.deserialize(&mut offset).unwrap());
+    }
+}
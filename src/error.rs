@@ -9,13 +9,177 @@ pub type ZeroFormatterResult<T> = Result<T, ZeroFormatterError>;
 pub enum ZeroFormatterError {
     IoError(io::Error),
     FromUtf8Error(FromUtf8Error),
-    InvalidBinary(u64)
+    InvalidBinary(u64),
+    Message(String),
+    /// The binary at the offset is invalid for the reason of `ErrorKind`.
+    Invalid(ErrorKind, u64),
+    /// The value or the registration is invalid for the reason of `ErrorKind`, apart from the binary.
+    InvalidValue(ErrorKind),
+    /// The error occurred in the field of `Path`.
+    InPath(Path, Box<ZeroFormatterError>)
+}
+
+/// `ErrorKind` describes why the binary or the value is invalid.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorKind {
+    /// A bool byte is neither 0 nor 1.
+    InvalidBool(u8),
+    /// The key is not a case of the union.
+    UnknownUnionKey,
+    /// The underlying integer is not a value of the enum of the name.
+    UnknownEnumValue(&'static str, i128),
+    /// A length or byteSize is negative other than -1 for null.
+    NegativeLength(i32),
+    /// An offset or an index in the table points outside of the binary.
+    OffsetOutOfRange(i32),
+    /// The chain of entries in a bucket of LazyDictionary format does not end.
+    CyclicChain,
+    /// Seconds or nanoseconds are out of the range of date and time.
+    InvalidDateTime,
+    /// A UTF-16 code unit is a surrogate, which is not a `char`.
+    InvalidChar(u16),
+    /// A `char` is outside the Basic Multilingual Plane, which is not a single UTF-16 code unit.
+    CharOutOfRange(char),
+    /// The field at the index is not written in Object format.
+    MissingField(i32),
+    /// The format is variable length where fixed length format is required.
+    NotFixedLength,
+    /// The flags of Decimal have unknown bits or the scale is greater than 28.
+    InvalidDecimal(u32),
+    /// The offset minutes of DateTimeOffset are out of the range of a time zone.
+    InvalidUtcOffset(i16),
+    /// The value is not of the type which is registered to DynamicUnion.
+    TypeMismatch,
+    /// The type of the value is not registered to DynamicUnion.
+    UnregisteredType,
+    /// The key is already registered to DynamicUnion.
+    DuplicateKey,
+    /// The type is already registered to DynamicUnion.
+    DuplicateType,
+    /// A byte size differs from the size which is expected.
+    SizeMismatch { expected: u64, actual: u64 },
+    /// A length does not fit in the integer of the format.
+    IntegerOverflow
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::InvalidBool(b) => write!(f, "{} is not a bool byte", b),
+            ErrorKind::UnknownUnionKey => f.write_str("unknown union key"),
+            ErrorKind::UnknownEnumValue(name, n) => write!(f, "{} is not a value of {}", n, name),
+            ErrorKind::NegativeLength(n) => write!(f, "negative length {}", n),
+            ErrorKind::OffsetOutOfRange(o) => write!(f, "offset {} is out of range", o),
+            ErrorKind::CyclicChain => f.write_str("cyclic chain of entries"),
+            ErrorKind::InvalidDateTime => f.write_str("invalid date and time"),
+            ErrorKind::InvalidChar(n) => write!(f, "{:#06x} is not a UTF-16 code unit of a char", n),
+            ErrorKind::CharOutOfRange(c) => write!(f, "{:?} cannot be encoded as a single UTF-16 code unit", c),
+            ErrorKind::MissingField(i) => write!(f, "field {} is not written", i),
+            ErrorKind::NotFixedLength => f.write_str("the format is not fixed length"),
+            ErrorKind::InvalidDecimal(flags) => write!(f, "{:#010x} is not flags of a decimal", flags),
+            ErrorKind::InvalidUtcOffset(m) => write!(f, "{} minutes is not a UTC offset", m),
+            ErrorKind::TypeMismatch => f.write_str("value does not match the registered type"),
+            ErrorKind::UnregisteredType => f.write_str("the type of the value is not registered"),
+            ErrorKind::DuplicateKey => f.write_str("the key is already registered"),
+            ErrorKind::DuplicateType => f.write_str("the type is already registered"),
+            ErrorKind::SizeMismatch { expected, actual } => write!(f, "expected {} bytes, but {} bytes", expected, actual),
+            ErrorKind::IntegerOverflow => f.write_str("integer overflow")
+        }
+    }
+}
+
+/// `PathSegment` is a field name or an element index of `Path`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PathSegment {
+    Field(&'static str),
+    Index(usize)
+}
+
+/// `Path` locates a value from the outermost type, like `Order.items[3].price`.
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+pub struct Path {
+    pub type_name: Option<&'static str>,
+    pub segments: Vec<PathSegment>
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(type_name) = self.type_name {
+            try!(f.write_str(type_name));
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            match *segment {
+                PathSegment::Field(name) if i == 0 && self.type_name.is_none() => try!(f.write_str(name)),
+                PathSegment::Field(name) => try!(write!(f, ".{}", name)),
+                PathSegment::Index(index) => try!(write!(f, "[{}]", index))
+            }
+        }
+        Ok(())
+    }
 }
 
 impl ZeroFormatterError {
     pub fn invalid_binary<T>(offset: u64) -> ZeroFormatterResult<T> {
         Err(ZeroFormatterError::InvalidBinary(offset))
     }
+
+    pub fn invalid<T>(kind: ErrorKind, offset: u64) -> ZeroFormatterResult<T> {
+        Err(ZeroFormatterError::Invalid(kind, offset))
+    }
+
+    fn prepend(self, type_name: Option<&'static str>, segment: PathSegment) -> ZeroFormatterError {
+        match self {
+            ZeroFormatterError::InPath(mut path, e) => {
+                path.type_name = type_name;
+                path.segments.insert(0, segment);
+                ZeroFormatterError::InPath(path, e)
+            },
+            e => ZeroFormatterError::InPath(Path { type_name, segments: vec![segment] }, Box::new(e))
+        }
+    }
+
+    /// Add the field of the type to the head of the path.
+    pub fn in_field(self, type_name: &'static str, field: &'static str) -> ZeroFormatterError {
+        self.prepend(Some(type_name), PathSegment::Field(field))
+    }
+
+    /// Add the element index to the head of the path.
+    pub fn at_index(self, index: usize) -> ZeroFormatterError {
+        self.prepend(None, PathSegment::Index(index))
+    }
+
+    /// Return the kind of the error, ignoring the path.
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match *self {
+            ZeroFormatterError::Invalid(kind, _) | ZeroFormatterError::InvalidValue(kind) => Some(kind),
+            ZeroFormatterError::InPath(_, ref e) => e.kind(),
+            _ => None
+        }
+    }
+
+    /// Return the offset of the invalid binary, ignoring the path.
+    pub fn offset(&self) -> Option<u64> {
+        match *self {
+            ZeroFormatterError::InvalidBinary(offset) | ZeroFormatterError::Invalid(_, offset) => Some(offset),
+            ZeroFormatterError::InPath(_, ref e) => e.offset(),
+            _ => None
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            ZeroFormatterError::InPath(ref path, _) => Some(path),
+            _ => None
+        }
+    }
+
+    fn io_error_kind(&self) -> io::ErrorKind {
+        match *self {
+            ZeroFormatterError::IoError(ref e) => e.kind(),
+            ZeroFormatterError::InPath(_, ref e) => e.io_error_kind(),
+            _ => io::ErrorKind::InvalidData
+        }
+    }
 }
 
 impl fmt::Display for ZeroFormatterError {
@@ -23,7 +187,12 @@ impl fmt::Display for ZeroFormatterError {
         match *self {
             ZeroFormatterError::IoError(_) | ZeroFormatterError::FromUtf8Error(_) => fmt::Debug::fmt(self, f),
             ZeroFormatterError::InvalidBinary(ref offset) =>
-                write!(f, "[offset {}] Binary does not valid.", *offset)
+                write!(f, "[offset {}] Binary does not valid.", *offset),
+            ZeroFormatterError::Message(ref msg) => f.write_str(msg),
+            ZeroFormatterError::Invalid(ref kind, ref offset) =>
+                write!(f, "[offset {}] {}", *offset, kind),
+            ZeroFormatterError::InvalidValue(ref kind) => fmt::Display::fmt(kind, f),
+            ZeroFormatterError::InPath(ref path, ref e) => write!(f, "{}: {}", path, e)
         }
     }
 }
@@ -33,7 +202,11 @@ impl Error for ZeroFormatterError {
         match self {
             &ZeroFormatterError::IoError(ref e) => e.description(),
             &ZeroFormatterError::FromUtf8Error(ref e) => e.description(),
-            &ZeroFormatterError::InvalidBinary(_) => "Binary does not valid."
+            &ZeroFormatterError::InvalidBinary(_) => "Binary does not valid.",
+            &ZeroFormatterError::Message(ref msg) => msg,
+            &ZeroFormatterError::Invalid(_, _) => "Binary does not valid.",
+            &ZeroFormatterError::InvalidValue(_) => "Value does not valid.",
+            &ZeroFormatterError::InPath(_, ref e) => e.description()
         }
     }
 
@@ -41,7 +214,11 @@ impl Error for ZeroFormatterError {
         match self {
             &ZeroFormatterError::IoError(ref e) => Some(e),
             &ZeroFormatterError::FromUtf8Error(ref e) => Some(e),
-            &ZeroFormatterError::InvalidBinary(_) => None
+            &ZeroFormatterError::InvalidBinary(_) => None,
+            &ZeroFormatterError::Message(_) => None,
+            &ZeroFormatterError::Invalid(_, _) => None,
+            &ZeroFormatterError::InvalidValue(_) => None,
+            &ZeroFormatterError::InPath(_, ref e) => Some(&**e)
         }
     }
 }
@@ -63,7 +240,39 @@ impl From<ZeroFormatterError> for io::Error {
         match err {
             ZeroFormatterError::IoError(e) => e,
             e @ ZeroFormatterError::FromUtf8Error(_) => io::Error::new(io::ErrorKind::InvalidData, e),
-            e @ ZeroFormatterError::InvalidBinary(_) => io::Error::new(io::ErrorKind::InvalidData, e)
+            e @ ZeroFormatterError::InvalidBinary(_) => io::Error::new(io::ErrorKind::InvalidData, e),
+            e @ ZeroFormatterError::Message(_) => io::Error::new(io::ErrorKind::InvalidData, e),
+            e @ ZeroFormatterError::Invalid(_, _) => io::Error::new(io::ErrorKind::InvalidData, e),
+            e @ ZeroFormatterError::InvalidValue(_) => io::Error::new(io::ErrorKind::InvalidInput, e),
+            e @ ZeroFormatterError::InPath(_, _) => {
+                let kind = e.io_error_kind();
+                io::Error::new(kind, e)
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use error::*;
+
+    #[test]
+    fn display_path() {
+        let e = ZeroFormatterError::Invalid(ErrorKind::InvalidBool(2), 40)
+            .in_field("Item", "price")
+            .at_index(3)
+            .in_field("Order", "items");
+        assert_eq!(e.to_string(), "Order.items[3].price: [offset 40] 2 is not a bool byte");
+        assert_eq!(e.kind(), Some(ErrorKind::InvalidBool(2)));
+        assert_eq!(e.offset(), Some(40));
+    }
+
+    #[test]
+    fn display_path_without_type() {
+        let e = ZeroFormatterError::Invalid(ErrorKind::NegativeLength(-2), 8)
+            .in_field("Item", "name")
+            .at_index(1);
+        assert_eq!(e.path().unwrap().to_string(), "[1].name");
+    }
+}
@@ -1,10 +1,21 @@
-use error::ZeroFormatterResult;
+use error::{ZeroFormatterResult, ZeroFormatterError, ErrorKind};
 
-use std::io::Seek;
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use buffer::ByteBuffer;
 
 /// Formatter provide serializer and deserializer for ZeroFormatter.
-pub trait Formatter<T>: Seek + ReadBytesExt + WriteBytesExt {
+pub trait Formatter<T>: ByteBuffer {
     fn serialize(&mut self, offset: u64, value: T) -> ZeroFormatterResult<i32>;
     fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<T>;
+
+    /// Return byte size if `T` is fixed length format, otherwise `None`.
+    fn length() -> Option<i32> where Self: Sized {
+        None
+    }
+
+    /// Return byte size which `serialize` returns for `value`, without writing it.
+    /// The default implementation supports only fixed length format.
+    fn serialized_size(value: &T) -> ZeroFormatterResult<i32> where Self: Sized {
+        let _ = value;
+        Self::length().ok_or(ZeroFormatterError::InvalidValue(ErrorKind::NotFixedLength))
+    }
 }
@@ -0,0 +1,61 @@
+use error::*;
+use formatter::*;
+
+use buffer::ByteBuffer;
+use uuid::Uuid;
+
+/// `Uuid` is written in the same byte order as `Guid.ToByteArray()`,
+/// the first three groups are little endian and the rest are big endian.
+impl<R> Formatter<Uuid> for R where R: ByteBuffer {
+
+    fn serialize(&mut self, offset: u64, value: Uuid) -> ZeroFormatterResult<i32> {
+        try!(self.write_at(offset, &value.to_bytes_le()));
+        Ok(16)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Uuid> {
+        let mut bytes = [0; 16];
+        try!(self.read_at(*offset, &mut bytes));
+        *offset += 16;
+        Ok(Uuid::from_bytes_le(bytes))
+    }
+
+    fn length() -> Option<i32> {
+        Some(16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use uuid::Uuid;
+    use formatter::*;
+
+    #[test]
+    fn serialize_guid() {
+        let mut wtr = Cursor::new(Vec::new());
+        let input = Uuid::parse_str("00112233-4455-6677-8899-aabbccddeeff").unwrap();
+        assert_eq!(wtr.serialize(0, input).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]);
+    }
+
+    #[test]
+    fn deserialize_guid() {
+        let mut rdr = Cursor::new(vec![0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]);
+        let mut offset = 0;
+        let actual: Uuid = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 16);
+        assert_eq!(Uuid::parse_str("00112233-4455-6677-8899-aabbccddeeff").unwrap(), actual);
+    }
+
+    #[test]
+    fn serialize_deserialize_guid_some() {
+        let mut c = Cursor::new(Vec::new());
+        let input = Some(Uuid::parse_str("00112233-4455-6677-8899-aabbccddeeff").unwrap());
+        assert_eq!(c.serialize(0, input).unwrap(), 17);
+        let mut offset = 0;
+        let actual: Option<Uuid> = c.deserialize(&mut offset).unwrap();
+        assert_eq!(input, actual);
+    }
+}
@@ -1,42 +1,66 @@
 use error::*;
 use formatter::*;
 
-use std::io::Seek;
-use byteorder::{ReadBytesExt, WriteBytesExt};
-use chrono::{UTC, DateTime};
+use buffer::ByteBuffer;
+use chrono::{UTC, FixedOffset, DateTime};
 use std::time::Duration;
+#[cfg(feature = "rust_decimal")]
+use rust_decimal::Decimal;
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
 
+/// `has_value_formatter_methods` writes `[hasValue:bool][value:T]`.
+/// If `T` is fixed length format, `None` is padded with zeros to the same length as `Some`,
+/// so that it can be an element of FixedSizeList format or a field of Struct format.
 #[macro_export]
 macro_rules! has_value_formatter_methods {
     ($t:ty) => (
         fn serialize(&mut self, offset: u64, value: Option<$t>) -> ZeroFormatterResult<i32> {
             match value {
                 None => {
-                    self.serialize(offset, false)
+                    let r1 = try!(<Self as Formatter<bool>>::serialize(self, offset, false));
+                    match <Self as Formatter<$t>>::length() {
+                        Some(l) => Ok(r1 + try!($crate::util::write_bytes(self, offset + 1, &vec![0; l as usize]))),
+                        None => Ok(r1)
+                    }
                 },
                 Some(v) => {
-                    let r1 = try!(self.serialize(offset, true));
-                    let r2 = try!(self.serialize(offset + 1, v));
+                    let r1 = try!(<Self as Formatter<bool>>::serialize(self, offset, true));
+                    let r2 = try!(<Self as Formatter<$t>>::serialize(self, offset + 1, v));
                     Ok(r1 + r2)
                 }
             }
         }
 
         fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Option<$t>> {
-            let has_value: bool = try!(self.deserialize(offset));
+            let has_value: bool = try!(<Self as Formatter<bool>>::deserialize(self, offset));
             if has_value {
-                self.deserialize(offset).map(|v| Some(v))
+                <Self as Formatter<$t>>::deserialize(self, offset).map(|v| Some(v))
             }
             else {
+                if let Some(l) = <Self as Formatter<$t>>::length() {
+                    *offset += l as u64;
+                }
                 Ok(None)
             }
         }
+
+        fn length() -> Option<i32> {
+            <Self as Formatter<$t>>::length().map(|l| l + 1)
+        }
+
+        fn serialized_size(value: &Option<$t>) -> ZeroFormatterResult<i32> {
+            match *value {
+                None => Ok(1 + <Self as Formatter<$t>>::length().unwrap_or(0)),
+                Some(ref v) => <Self as Formatter<$t>>::serialized_size(v).map(|s| s + 1)
+            }
+        }
     )
 }
 
 macro_rules! primitive_has_value_formatter {
     ($($t:ty),*) => ($(
-        impl<R> Formatter<Option<$t>> for R where R: Seek + ReadBytesExt + WriteBytesExt {
+        impl<R> Formatter<Option<$t>> for R where R: ByteBuffer {
             has_value_formatter_methods! { $t }
         }
     )*)
@@ -54,10 +78,18 @@ primitive_has_value_formatter! {
     f32,
     f64,
     bool,
+    char,
     DateTime<UTC>,
+    DateTime<FixedOffset>,
     Duration
 }
 
+#[cfg(feature = "rust_decimal")]
+primitive_has_value_formatter! { Decimal }
+
+#[cfg(feature = "uuid")]
+primitive_has_value_formatter! { Uuid }
+
 #[macro_export]
 macro_rules! has_value_formatter {
     (#[target($buffer:ty)]
@@ -73,6 +105,7 @@ macro_rules! has_value_formatter {
 mod tests {
 
     use std::io::Cursor;
+    use chrono::{FixedOffset, DateTime, TimeZone};
     use formatter::*;
 
     #[test]
@@ -93,15 +126,47 @@ mod tests {
     fn serialize_u8_none() {
         let mut wtr = Cursor::new(Vec::new());
         let input: Option<u8> = None;
-        assert_eq!(wtr.serialize(0, input).unwrap(), 1);
-        assert_eq!(wtr.into_inner(), vec![0]);
+        assert_eq!(wtr.serialize(0, input).unwrap(), 2);
+        assert_eq!(wtr.into_inner(), vec![0, 0]);
     }
 
     #[test]
     fn deserialize_u8_none() {
-        let mut rdr = Cursor::new(vec![0]);
+        let mut rdr = Cursor::new(vec![0, 0]);
         let mut offset = 0;
         let expected: Option<u8> = None;
         assert_eq!(expected, rdr.deserialize(&mut offset).unwrap());
+        assert_eq!(offset, 2);
+    }
+
+    #[test]
+    fn serialize_char_some() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, Some('a')).unwrap(), 3);
+        assert_eq!(wtr.into_inner(), vec![1, 0x61, 0]);
+    }
+
+    #[test]
+    fn deserialize_char_none() {
+        let mut rdr = Cursor::new(vec![0]);
+        let mut offset = 0;
+        let expected: Option<char> = None;
+        assert_eq!(expected, rdr.deserialize(&mut offset).unwrap());
+    }
+
+    #[test]
+    fn serialize_deserialize_datetime_offset_some() {
+        let mut c = Cursor::new(Vec::new());
+        let input = Some(FixedOffset::east(3600).timestamp(1, 0));
+        assert_eq!(c.serialize(0, input).unwrap(), 15);
+        let mut offset = 0;
+        let actual: Option<DateTime<FixedOffset>> = c.deserialize(&mut offset).unwrap();
+        assert_eq!(input, actual);
+    }
+
+    #[test]
+    fn serialized_size_has_value() {
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Option<i32>>>::serialized_size(&Some(1)).unwrap(), 5);
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Option<i32>>>::serialized_size(&None).unwrap(), 5);
     }
 }
@@ -13,10 +13,8 @@
 //!
 //! ```
 //! #[macro_use] extern crate zero_formatter;
-//! extern crate byteorder;
 //! use zero_formatter::*;
-//! use std::io::{Seek, SeekFrom, Read, Write, Cursor, Result};
-//! use byteorder::{ReadBytesExt, WriteBytesExt};
+//! use std::io::{Cursor, Result};
 //!
 //! declare_buffer! { Buffer }
 //!
@@ -39,6 +37,47 @@
 //! # }
 //! ```
 //!
+//! `VecBuffer` is a faster buffer than `Cursor<Vec<u8>>` for in-memory use, because it does not seek.
+//! `to_vec` and `from_slice` serialize and deserialize a value through it, or through a buffer declared over it.
+//! `serialized_size` returns the byte size without writing, like `Formatter::serialized_size`.
+//!
+//! ```
+//! # extern crate zero_formatter;
+//! # use zero_formatter::*;
+//! #
+//! # fn main() {
+//! let bytes = to_vec::<VecBuffer, _>((1i32, 2i64)).unwrap();
+//! assert_eq!(serialized_size::<VecBuffer, _>(&(1i32, 2i64)).unwrap(), 12);
+//! assert_eq!((1i32, 2i64), from_slice::<VecBuffer, _>(&bytes).unwrap());
+//! # }
+//! ```
+//!
+//! ## Errors
+//!
+//! An invalid binary is reported as `ErrorKind` with its offset.
+//! Formatters defined by the macros add the path of the failed value, like `Order.items[3].price`.
+//!
+//! ```
+//! # extern crate zero_formatter;
+//! # use zero_formatter::*;
+//! #
+//! # fn main() {
+//! let e = from_slice::<VecBuffer, bool>(&[2]).unwrap_err();
+//! assert_eq!(e.kind(), Some(ErrorKind::InvalidBool(2)));
+//! assert_eq!(e.to_string(), "[offset 0] 2 is not a bool byte");
+//! # }
+//! ```
+//!
+//! ## Serde
+//!
+//! Enable `serde` feature to serialize and deserialize types which implement serde traits.
+//! See `ser` and `de` modules.
+//!
+//! ```toml
+//! [dependencies]
+//! zero-formatter = { version = "0.1", features = ["serde"] }
+//! ```
+//!
 //! ## Supported Type
 //!
 //! Currently, this library support only [Stage1](https://github.com/neuecc/ZeroFormatter/tree/1.6.0#cross-platform).
@@ -59,10 +98,14 @@
 //! | `bool` | `Boolean` | |
 //! | `u8` | `Byte` | |
 //! | `i8` | `SByte` | |
+//! | `char` | `Char` | UTF-16 code unit, surrogate pairs are not supported |
 //! | `time::Duration` | `TimeSpan` | |
 //! | `chrono::DateTime<chrono::UTC>` | `DateTime` | |
-//! | | `DateTimeOffset` | |
+//! | `chrono::DateTime<chrono::FixedOffset>` | `DateTimeOffset` | |
+//! | `rust_decimal::Decimal` | `Decimal` | requires `rust_decimal` feature |
+//! | `uuid::Uuid` | `Guid` | requires `uuid` feature |
 //! | `Cow<'a, str>` | `String` | |
+//! | `String` | `String` | |
 //! | `Option<i16>` | `Int16?` | |
 //! | `Option<i32>` | `Int32?`| |
 //! | `Option<i64>` | `Int64?` | |
@@ -74,60 +117,135 @@
 //! | `Option<bool>` | `Boolean?` | |
 //! | `Option<u8>` | `Byte?` | |
 //! | `Option<i8>` | `SByte?` | |
+//! | `Option<char>` | `Char?` | |
 //! | `Option<time::Duration>` | `TimeSpan?` | |
 //! | `Option<chrono::DateTime<chrono::UTC>>` | `DateTime?` | |
-//! | | `DateTimeOffset?` | |
+//! | `Option<chrono::DateTime<chrono::FixedOffset>>` | `DateTimeOffset?` | |
+//! | `Option<rust_decimal::Decimal>` | `Decimal?` | requires `rust_decimal` feature |
+//! | `Option<Cow<'a, str>>` | `String` | if length = -1, indicates `None` |
+//! | `Option<String>` | `String` | if length = -1, indicates `None` |
+//! | enum | Enum | use `enum_formatter` macro |
+//! | `Option<enum>` | Enum? | |
+//! | `Option<uuid::Uuid>` | `Guid?` | requires `uuid` feature |
 //!
 //! ### Sequence Format
 //!
 //! | Rust | C# | Note |
 //! | ---- | ---- | --- |
 //! | `Cow<'a, [T]>` | `Sequence<T>` | |
+//! | `Option<Cow<'a, [T]>>` | `Sequence<T>` | if length = -1, indicates `None` |
 //!
 //! ### List Format
 //!
 //! | Rust | C# | Note |
 //! | ---- | ---- | --- |
-//! | | FixedSizeList | |
-//! | | VariableSizeList | |
+//! | `Vec<T>` | FixedSizeList | if `T` is fixed length format |
+//! | `FixedSizeList<R, T>` | FixedSizeList | lazy view, read only the requested element |
+//! | `Vec<T>` | VariableSizeList | if `T` is variable length format |
+//! | `VariableSizeList<R, T>` | VariableSizeList | lazy view, read only the requested element |
+//! | `Option<Vec<T>>` | FixedSizeList / VariableSizeList | if length or byteSize = -1, indicates `None` |
+//!
+//! ### Dictionary Format
+//!
+//! | Rust | C# | Note |
+//! | ---- | ---- | --- |
+//! | `HashMap<K, V>` | Dictionary | |
+//! | `BTreeMap<K, V>` | Dictionary | |
+//! | `Option<HashMap<K, V>>` | Dictionary | if length = -1, indicates `None` |
+//! | `Option<BTreeMap<K, V>>` | Dictionary | if length = -1, indicates `None` |
+//! | `Lazy<HashMap<K, V>>` | LazyDictionary | `K` must implement `HashCode` |
+//! | `Option<Lazy<HashMap<K, V>>>` | LazyDictionary | if byteSize = -1, indicates `None` |
+//! | `LazyDictionary<R, K, V>` | LazyDictionary | lazy view, read only the requested value |
+//! | `Lookup<K, V>` | Lookup | |
+//! | `Option<Lookup<K, V>>` | Lookup | if length = -1, indicates `None` |
+//! | `Lazy<Lookup<K, V>>` | LazyLookup | `K` must implement `HashCode` |
+//! | `Option<Lazy<Lookup<K, V>>>` | LazyLookup | if byteSize = -1, indicates `None` |
+//! | `LazyLookup<R, K, V>` | LazyLookup | lazy view, read only the requested group |
 //!
 //! ### Object Format
 //!
 //! | Rust | C# | Note |
 //! | ---- | ---- | --- |
-//! | struct | Object | use `object_formatter` macro |
+//! | struct | Object | use `object_formatter` macro or `#[derive(ZeroFormatter)]` of `zero-formatter-derive` |
 //! | `Option<struct>` | Object | if byteSize = -1, indicates `None` |
+//! | view struct | Object | lazy view, use `#[view(Name)]` of `object_formatter` or `object_view` macro |
+//! | `Tracked<struct>` | Object | copy unchanged fields from the original bytes, use `object_formatter` or `tracked_formatter` macro |
 //! | struct | Struct | |
 //! | `Option<struct>` | Struct? | |
-//! | `Option<(A1, A2)>` | Tuple<A1, A2> | |
+//! | `(A1, ..., An)` | Struct | n = 1 to 8 |
+//! | `Option<(A1, ..., An)>` | Tuple<A1, ..., An> | n = 1 to 8 |
+//! | `KeyTuple<(A1, ..., An)>` | KeyTuple<A1, ..., An> | can be a dictionary key |
+//! | `Option<KeyTuple<(A1, ..., An)>>` | KeyTuple<A1, ..., An>? | |
 //!
 //! ### Union Format
 //!
 //! | Rust | C# | Note |
 //! | ---- | ---- | --- |
 //! | enum | Union | use `union_formatter` macro |
+//! | enum with `Cow<str>`, `String` or enum keys | Union with string or enum keys | keys implement `UnionKey` |
+//! | enum with `#[fallback]` | Union with `[UnionFallback]` | keeps the key and the bytes of unknown cases |
 //! | Option<enum> | | if byte_size = 1, indicates `None` |
+//! | `DynamicUnion<R, K>` | DynamicUnion | registers cases at runtime, and values are `Box<dyn Any>` |
 
 
 extern crate byteorder;
 extern crate chrono;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "rust_decimal")]
+extern crate rust_decimal;
+#[cfg(feature = "uuid")]
+extern crate uuid;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
 
 mod error;
 mod formatter;
 #[macro_use]
 mod buffer;
-mod primitive;
 pub mod util;
 #[macro_use]
 mod has_value;
 #[macro_use]
 mod option;
+mod primitive;
+#[macro_use]
+mod tracked;
 #[macro_use]
 mod object;
+mod vec_buffer;
 mod time;
+#[cfg(feature = "rust_decimal")]
+mod decimal;
+#[cfg(feature = "uuid")]
+mod guid;
 mod sequence;
+mod list;
+mod dictionary;
+mod lookup;
+#[macro_use]
 mod union;
+mod dynamic_union;
+#[macro_use]
+mod enums;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "serde")]
+pub mod nullable;
 
 pub use error::ZeroFormatterResult;
 pub use error::ZeroFormatterError;
+pub use error::{ErrorKind, Path, PathSegment};
 pub use formatter::Formatter;
+pub use buffer::{ByteBuffer, MemoryBuffer};
+pub use vec_buffer::{VecBuffer, to_vec, from_slice, serialized_size};
+pub use list::{FixedSizeList, VariableSizeList};
+pub use dictionary::{Lazy, HashCode, DictionaryEntry, LazyDictionary};
+pub use lookup::{Lookup, LazyLookup};
+pub use object::KeyTuple;
+pub use tracked::Tracked;
+pub use union::UnionKey;
+pub use dynamic_union::DynamicUnion;
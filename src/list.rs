@@ -0,0 +1,528 @@
+use error::*;
+use formatter::*;
+use util;
+use buffer::ByteBuffer;
+
+use std::marker::PhantomData;
+
+fn not_fixed_length<T>() -> ZeroFormatterResult<T> {
+    Err(ZeroFormatterError::InvalidValue(ErrorKind::NotFixedLength))
+}
+
+/// Return `header + element_size * length`, or `IntegerOverflow` error.
+fn list_size(header: i32, element_size: i32, length: usize, offset: u64) -> ZeroFormatterResult<i32> {
+    let length = try!(util::length_to_i32(length, offset));
+    match element_size.checked_mul(length).and_then(|n| n.checked_add(header)) {
+        Some(n) => Ok(n),
+        None => ZeroFormatterError::invalid(ErrorKind::IntegerOverflow, offset)
+    }
+}
+
+fn serialize_fixed_size_list<R, A>(r: &mut R, offset: u64, value: Vec<A>, element_size: i32) -> ZeroFormatterResult<i32>
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+    let length = try!(util::length_to_i32(value.len(), offset));
+    let lr = try!(r.serialize(offset, length));
+    let mut o = offset + (lr as u64);
+    for a in value {
+        try!(r.serialize(o, a));
+        o += element_size as u64;
+    }
+    Ok((o - offset) as i32)
+}
+
+fn deserialize_fixed_size_list<R, A>(r: &mut R, offset: &mut u64, element_size: i32) -> ZeroFormatterResult<Vec<A>>
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+    let l: i32 = try!(util::check_non_null(r, offset));
+    let start = *offset;
+    let mut v: Vec<A> = Vec::with_capacity(l as usize);
+    for i in 0..(l as usize) {
+        let mut o = start + (i as u64) * (element_size as u64);
+        v.push(try!(r.deserialize(&mut o).map_err(|e| e.at_index(i))));
+    }
+    *offset = start + (l as u64) * (element_size as u64);
+    Ok(v)
+}
+
+pub fn serialize_variable_size_list<R, A>(r: &mut R, offset: u64, value: Vec<A>) -> ZeroFormatterResult<i32>
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+    let length = try!(util::length_to_i32(value.len(), offset));
+    let mut byte_size: i32 = try!(list_size(4 + 4, 4, value.len(), offset));
+    try!(r.serialize(offset + 4, length));
+    for (i, a) in value.into_iter().enumerate() {
+        try!(r.serialize(offset + 4 + 4 + 4 * (i as u64), (offset as i32) + byte_size));
+        byte_size += try!(r.serialize(offset + (byte_size as u64), a));
+    }
+    try!(r.serialize(offset, byte_size));
+    Ok(byte_size)
+}
+
+pub fn variable_size_list_serialized_size<R, A>(value: &[A]) -> ZeroFormatterResult<i32>
+    where R: ByteBuffer + Formatter<A> {
+    let mut byte_size: i32 = try!(list_size(4 + 4, 4, value.len(), 0));
+    for a in value {
+        let s = try!(<R as Formatter<A>>::serialized_size(a));
+        byte_size = match byte_size.checked_add(s) {
+            Some(n) => n,
+            None => return ZeroFormatterError::invalid(ErrorKind::IntegerOverflow, 0)
+        };
+    }
+    Ok(byte_size)
+}
+
+pub fn deserialize_variable_size_list<R, A>(r: &mut R, offset: &mut u64) -> ZeroFormatterResult<Vec<A>>
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+    let start_offset = *offset;
+    let byte_size: i32 = try!(util::check_non_null(r, offset));
+    let length: i32 = try!(util::check_non_null(r, offset));
+    let mut v: Vec<A> = Vec::with_capacity(length as usize);
+    for i in 0..(length as usize) {
+        let mut o = try!(util::check_non_null(r, offset)) as u64;
+        v.push(try!(r.deserialize(&mut o).map_err(|e| e.at_index(i))));
+    }
+    *offset = start_offset + (byte_size as u64);
+    Ok(v)
+}
+
+/// `Vec<T>` is FixedSizeList format if `T` is fixed length format, otherwise VariableSizeList format.
+impl<R, A> Formatter<Vec<A>> for R
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+
+    fn serialize(&mut self, offset: u64, value: Vec<A>) -> ZeroFormatterResult<i32> {
+        match <R as Formatter<A>>::length() {
+            Some(l) => serialize_fixed_size_list(self, offset, value, l),
+            None => serialize_variable_size_list(self, offset, value)
+        }
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Vec<A>> {
+        match <R as Formatter<A>>::length() {
+            Some(l) => deserialize_fixed_size_list(self, offset, l),
+            None => deserialize_variable_size_list(self, offset)
+        }
+    }
+
+    fn serialized_size(value: &Vec<A>) -> ZeroFormatterResult<i32> {
+        match <R as Formatter<A>>::length() {
+            Some(l) => list_size(4, l, value.len(), 0),
+            None => variable_size_list_serialized_size::<R, A>(value)
+        }
+    }
+}
+
+/// `Option<Vec<T>>` writes -1 as length (FixedSizeList) or byteSize (VariableSizeList) for `None`.
+impl<R, A> Formatter<Option<Vec<A>>> for R
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+
+    option_formatter_methods! { Vec<A> }
+}
+
+fn is_null<R>(buffer: &mut R, offset: &mut u64) -> ZeroFormatterResult<bool>
+    where R: ByteBuffer + Formatter<i32> {
+    let mut o = *offset;
+    let header: i32 = try!(buffer.deserialize(&mut o));
+    if header == -1 {
+        *offset = o;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// `FixedSizeList` is a lazy view of FixedSizeList format.
+/// It reads only the requested element.
+///
+/// ```
+/// # extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::Cursor;
+/// #
+/// # fn main() {
+/// let mut buffer = Cursor::new(Vec::new());
+/// buffer.serialize(0, vec![1i32, 2i32, 3i32]).unwrap();
+///
+/// let mut offset = 0;
+/// let mut list: FixedSizeList<_, i32> = FixedSizeList::new(&mut buffer, &mut offset).unwrap();
+/// assert_eq!(list.len(), 3);
+/// assert_eq!(list.get(1).unwrap(), Some(2));
+/// assert_eq!(list.get(3).unwrap(), None);
+/// # }
+/// ```
+pub struct FixedSizeList<'a, R: 'a, A> {
+    buffer: &'a mut R,
+    offset: u64,
+    length: i32,
+    element_size: i32,
+    _element: PhantomData<A>
+}
+
+impl<'a, R, A> FixedSizeList<'a, R, A>
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+
+    /// Read the length header at `offset`, and move `offset` to the end of the list.
+    pub fn new(buffer: &'a mut R, offset: &mut u64) -> ZeroFormatterResult<FixedSizeList<'a, R, A>> {
+        let element_size = match <R as Formatter<A>>::length() {
+            Some(l) => l,
+            None => return not_fixed_length()
+        };
+        let length: i32 = try!(util::check_non_null(buffer, offset));
+        let start = *offset;
+        *offset += (length as u64) * (element_size as u64);
+        Ok(FixedSizeList {
+            buffer,
+            offset: start,
+            length,
+            element_size,
+            _element: PhantomData
+        })
+    }
+
+    /// Same as `new`, but return `None` if length is -1.
+    pub fn new_nullable(buffer: &'a mut R, offset: &mut u64) -> ZeroFormatterResult<Option<FixedSizeList<'a, R, A>>> {
+        if try!(is_null(buffer, offset)) {
+            Ok(None)
+        } else {
+            FixedSizeList::new(buffer, offset).map(Some)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Read the element at `index`. Return `None` if `index` is out of range.
+    pub fn get(&mut self, index: usize) -> ZeroFormatterResult<Option<A>> {
+        if index >= self.len() {
+            return Ok(None);
+        }
+        let mut offset = self.offset + (index as u64) * (self.element_size as u64);
+        self.buffer.deserialize(&mut offset).map(Some)
+    }
+}
+
+/// `VariableSizeList` is a lazy view of VariableSizeList format.
+/// It jumps to the requested element through the offset table.
+///
+/// ```
+/// # extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::Cursor;
+/// # use std::borrow::Cow;
+/// #
+/// # fn main() {
+/// let mut buffer = Cursor::new(Vec::new());
+/// let input: Vec<Cow<'static, str>> = vec![Cow::Borrowed("a"), Cow::Borrowed("bc")];
+/// buffer.serialize(0, input).unwrap();
+///
+/// let mut offset = 0;
+/// let mut list: VariableSizeList<_, Cow<'static, str>> = VariableSizeList::new(&mut buffer, &mut offset).unwrap();
+/// assert_eq!(list.len(), 2);
+/// assert_eq!(list.get(1).unwrap(), Some(Cow::Borrowed("bc")));
+/// # }
+/// ```
+pub struct VariableSizeList<'a, R: 'a, A> {
+    buffer: &'a mut R,
+    offset: u64,
+    length: i32,
+    _element: PhantomData<A>
+}
+
+impl<'a, R, A> VariableSizeList<'a, R, A>
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+
+    /// Read the header at `offset`, and move `offset` to the end of the list.
+    pub fn new(buffer: &'a mut R, offset: &mut u64) -> ZeroFormatterResult<VariableSizeList<'a, R, A>> {
+        let start = *offset;
+        let byte_size: i32 = try!(util::check_non_null(buffer, offset));
+        let length: i32 = try!(util::check_non_null(buffer, offset));
+        *offset = start + (byte_size as u64);
+        Ok(VariableSizeList {
+            buffer,
+            offset: start,
+            length,
+            _element: PhantomData
+        })
+    }
+
+    /// Same as `new`, but return `None` if byteSize is -1.
+    pub fn new_nullable(buffer: &'a mut R, offset: &mut u64) -> ZeroFormatterResult<Option<VariableSizeList<'a, R, A>>> {
+        if try!(is_null(buffer, offset)) {
+            Ok(None)
+        } else {
+            VariableSizeList::new(buffer, offset).map(Some)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Read the element at `index`. Return `None` if `index` is out of range.
+    pub fn get(&mut self, index: usize) -> ZeroFormatterResult<Option<A>> {
+        if index >= self.len() {
+            return Ok(None);
+        }
+        let mut o = self.offset + 4 + 4 + 4 * (index as u64);
+        let mut element_offset = try!(util::check_non_null(self.buffer, &mut o)) as u64;
+        self.buffer.deserialize(&mut element_offset).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use std::time::Duration;
+    use std::borrow::Cow;
+    use error::*;
+    use formatter::*;
+    use list::*;
+
+    #[test]
+    fn serialize_fixed_size_list() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, vec![1i32, 2i32, 3i32]).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn deserialize_fixed_size_list() {
+        let mut rdr = Cursor::new(vec![3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: Vec<i32> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 16);
+        assert_eq!(vec![1, 2, 3], actual);
+    }
+
+    #[test]
+    fn serialize_variable_size_list() {
+        let mut wtr = Cursor::new(Vec::new());
+        let input: Vec<Cow<'static, str>> = vec![Cow::Borrowed("a"), Cow::Borrowed("bc")];
+        assert_eq!(wtr.serialize(0, input).unwrap(), 27);
+        assert_eq!(wtr.into_inner(), vec![
+            27, 0, 0, 0,
+            2, 0, 0, 0,
+            16, 0, 0, 0,
+            21, 0, 0, 0,
+            1, 0, 0, 0, 0x61,
+            2, 0, 0, 0, 0x62, 0x63
+        ]);
+    }
+
+    #[test]
+    fn deserialize_variable_size_list() {
+        let mut rdr = Cursor::new(vec![
+            27, 0, 0, 0,
+            2, 0, 0, 0,
+            16, 0, 0, 0,
+            21, 0, 0, 0,
+            1, 0, 0, 0, 0x61,
+            2, 0, 0, 0, 0x62, 0x63
+        ]);
+        let mut offset = 0;
+        let actual: Vec<Cow<'static, str>> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 27);
+        assert_eq!(vec![Cow::Borrowed("a"), Cow::Borrowed("bc")], actual);
+    }
+
+    #[test]
+    fn lazy_fixed_size_list() {
+        let mut rdr = Cursor::new(vec![3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+        let mut offset = 0;
+        let mut list: FixedSizeList<_, i32> = FixedSizeList::new(&mut rdr, &mut offset).unwrap();
+        assert_eq!(offset, 16);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.get(2).unwrap(), Some(3));
+        assert_eq!(list.get(0).unwrap(), Some(1));
+        assert_eq!(list.get(3).unwrap(), None);
+    }
+
+    #[test]
+    fn lazy_fixed_size_list_duration() {
+        let mut c = Cursor::new(Vec::new());
+        assert_eq!(c.serialize(0, vec![Duration::new(1, 2), Duration::new(3, 4)]).unwrap(), 28);
+        let mut offset = 0;
+        let mut list: FixedSizeList<_, Duration> = FixedSizeList::new(&mut c, &mut offset).unwrap();
+        assert_eq!(list.get(1).unwrap(), Some(Duration::new(3, 4)));
+    }
+
+    struct_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        S {
+            a: i32,
+            b: Option<i16>
+        }
+    }
+
+    #[test]
+    fn serialize_deserialize_fixed_size_list_option() {
+        for input in &[vec![None, Some(5i32), Some(6)], vec![Some(5i32), None, Some(6)], vec![Some(5i32), Some(6), None]] {
+            let mut c = Cursor::new(Vec::new());
+            assert_eq!(c.serialize(0, input.clone()).unwrap(), 4 + 3 * 5);
+            assert_eq!(c.get_ref().len(), 4 + 3 * 5);
+            let mut offset = 0;
+            let actual: Vec<Option<i32>> = c.deserialize(&mut offset).unwrap();
+            assert_eq!(offset, 4 + 3 * 5);
+            assert_eq!(*input, actual);
+        }
+    }
+
+    #[test]
+    fn serialize_fixed_size_list_none_padding() {
+        let mut wtr = Cursor::new(Vec::new());
+        wtr.serialize(0, vec![Some(5i32), None]).unwrap();
+        assert_eq!(wtr.into_inner(), vec![2, 0, 0, 0, 1, 5, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serialize_deserialize_fixed_size_list_struct() {
+        for input in &[
+            vec![S { a: 1, b: None }, S { a: 2, b: Some(3) }, S { a: 4, b: Some(5) }],
+            vec![S { a: 1, b: Some(3) }, S { a: 2, b: None }, S { a: 4, b: Some(5) }],
+            vec![S { a: 1, b: Some(3) }, S { a: 2, b: Some(5) }, S { a: 4, b: None }]
+        ] {
+            let mut c = Cursor::new(Vec::new());
+            assert_eq!(c.serialize(0, input.clone()).unwrap(), 4 + 3 * 7);
+            assert_eq!(c.get_ref().len(), 4 + 3 * 7);
+            let mut offset = 0;
+            let actual: Vec<S> = c.deserialize(&mut offset).unwrap();
+            assert_eq!(offset, 4 + 3 * 7);
+            assert_eq!(*input, actual);
+        }
+    }
+
+    #[test]
+    fn lazy_fixed_size_list_struct() {
+        let mut c = Cursor::new(Vec::new());
+        let input = vec![S { a: 1, b: None }, S { a: 2, b: Some(3) }];
+        assert_eq!(c.serialize(0, input).unwrap(), 18);
+        let mut offset = 0;
+        let mut list: FixedSizeList<_, S> = FixedSizeList::new(&mut c, &mut offset).unwrap();
+        assert_eq!(list.get(1).unwrap(), Some(S { a: 2, b: Some(3) }));
+    }
+
+    object_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        O {
+            0; a: i32
+        }
+    }
+
+    #[test]
+    fn lazy_fixed_size_list_not_fixed_length() {
+        let mut rdr = Cursor::new(vec![0, 0, 0, 0]);
+        let mut offset = 0;
+        let list: ZeroFormatterResult<FixedSizeList<_, O>> = FixedSizeList::new(&mut rdr, &mut offset);
+        assert_eq!(list.err().and_then(|e| e.kind()), Some(ErrorKind::NotFixedLength));
+    }
+
+    #[test]
+    fn serialize_deserialize_variable_size_list_object() {
+        let mut c = Cursor::new(Vec::new());
+        assert_eq!(c.serialize(4, vec![O { a: 1 }, O { a: 2 }]).unwrap(), 48);
+        let mut offset = 4;
+        let actual: Vec<O> = c.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 52);
+        assert_eq!(vec![O { a: 1 }, O { a: 2 }], actual);
+    }
+
+    #[test]
+    fn lazy_variable_size_list() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, vec![O { a: 1 }, O { a: 2 }, O { a: 3 }]).unwrap();
+        let mut offset = 0;
+        let mut list: VariableSizeList<_, O> = VariableSizeList::new(&mut c, &mut offset).unwrap();
+        assert_eq!(offset, 68);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.get(2).unwrap(), Some(O { a: 3 }));
+        assert_eq!(list.get(0).unwrap(), Some(O { a: 1 }));
+        assert_eq!(list.get(3).unwrap(), None);
+    }
+
+    #[test]
+    fn serialize_list_none() {
+        let mut wtr = Cursor::new(Vec::new());
+        let input: Option<Vec<Cow<str>>> = None;
+        assert_eq!(wtr.serialize(0, input).unwrap(), 4);
+        assert_eq!(wtr.into_inner(), vec![0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn serialize_deserialize_list_some() {
+        let mut c = Cursor::new(Vec::new());
+        let input = Some(vec![Cow::Borrowed("a")]);
+        let byte_size = c.serialize(0, input.clone()).unwrap();
+        let mut offset = 0;
+        let actual: Option<Vec<Cow<'static, str>>> = c.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, byte_size as u64);
+        assert_eq!(input, actual);
+    }
+
+    #[test]
+    fn deserialize_fixed_size_list_none() {
+        let mut rdr = Cursor::new(vec![0xff, 0xff, 0xff, 0xff]);
+        let mut offset = 0;
+        let actual: Option<Vec<i32>> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 4);
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn lazy_list_nullable() {
+        let mut c = Cursor::new(Vec::new());
+        let input: Option<Vec<i32>> = None;
+        c.serialize(0, input).unwrap();
+        c.serialize(4, vec![1i32]).unwrap();
+        let mut offset = 0;
+        assert!(FixedSizeList::<_, i32>::new_nullable(&mut c, &mut offset).unwrap().is_none());
+        assert_eq!(offset, 4);
+        let mut list = FixedSizeList::<_, i32>::new_nullable(&mut c, &mut offset).unwrap().unwrap();
+        assert_eq!(list.get(0).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn lazy_variable_size_list_nullable() {
+        let mut rdr = Cursor::new(vec![0xff, 0xff, 0xff, 0xff]);
+        let mut offset = 0;
+        assert!(VariableSizeList::<_, Cow<str>>::new_nullable(&mut rdr, &mut offset).unwrap().is_none());
+        assert_eq!(offset, 4);
+    }
+
+    #[test]
+    fn serialized_size_fixed_size_list() {
+        let input = vec![1i32, 2, 3];
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Vec<i32>>>::serialized_size(&input).unwrap(), 16);
+    }
+
+    #[test]
+    fn serialized_size_fixed_size_list_option() {
+        let input = vec![Some(1i32), None, Some(2), None];
+        let mut wtr = Cursor::new(Vec::new());
+        let expected = wtr.serialize(0, input.clone()).unwrap();
+        assert_eq!(expected as usize, wtr.get_ref().len());
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Vec<Option<i32>>>>::serialized_size(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn list_size_overflow() {
+        assert_eq!(list_size(4, 4, 3, 0).unwrap(), 16);
+        assert_eq!(list_size(4, 4, (i32::MAX / 4) as usize, 0).unwrap_err().kind(), Some(ErrorKind::IntegerOverflow));
+        assert_eq!(list_size(4 + 4, 4, i32::MAX as usize, 0).unwrap_err().kind(), Some(ErrorKind::IntegerOverflow));
+        assert_eq!(list_size(4, 1, usize::MAX, 0).unwrap_err().kind(), Some(ErrorKind::IntegerOverflow));
+    }
+
+    #[test]
+    fn serialized_size_variable_size_list() {
+        let input: Vec<Cow<'static, str>> = vec![Cow::Borrowed("a"), Cow::Borrowed("bc")];
+        let mut wtr = Cursor::new(Vec::new());
+        let expected = wtr.serialize(0, input.clone()).unwrap();
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Vec<Cow<str>>>>::serialized_size(&input).unwrap(), expected);
+    }
+}
@@ -0,0 +1,286 @@
+use error::*;
+use formatter::*;
+use util;
+use dictionary;
+use dictionary::{Lazy, HashCode, LazyDictionary};
+use buffer::ByteBuffer;
+
+use std::hash::Hash;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// `Lookup` is a collection of keys each mapped to one or more values, like `ILookup` of C#.
+///
+/// | Rust | C# | Layout |
+/// | ---- | ---- | ---- |
+/// | `Lookup<K, V>` | Lookup | `[length:int(-1 is null)][(key:K, elements:Sequence<V>)...]` |
+/// | `Lazy<Lookup<K, V>>` | LazyLookup | `[byteSize:int(-1 is null)][length:int][groupings:LazyDictionary<K, Sequence<V>>]` |
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lookup<K: Eq + Hash, V>(pub HashMap<K, Vec<V>>);
+
+impl<K: Eq + Hash, V> Default for Lookup<K, V> {
+    fn default() -> Lookup<K, V> {
+        Lookup::new()
+    }
+}
+
+impl<K: Eq + Hash, V> Lookup<K, V> {
+
+    pub fn new() -> Lookup<K, V> {
+        Lookup(HashMap::new())
+    }
+
+    /// Append `value` to the group of `key`.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.0.entry(key).or_default().push(value);
+    }
+
+    /// Return the group of `key`. If `key` is not found, return an empty slice.
+    pub fn get(&self, key: &K) -> &[V] {
+        self.0.get(key).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// Return the number of groups.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn groupings_serialized_size<R, V>(vs: &[V]) -> ZeroFormatterResult<i32>
+    where R: ByteBuffer + Formatter<V> {
+    let mut byte_size: i32 = 4;
+    for v in vs {
+        byte_size += try!(<R as Formatter<V>>::serialized_size(v));
+    }
+    Ok(byte_size)
+}
+
+impl<R, K, V> Formatter<Lookup<K, V>> for R
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>, K: Eq + Hash, V: Clone {
+
+    fn serialize(&mut self, offset: u64, value: Lookup<K, V>) -> ZeroFormatterResult<i32> {
+        let length = try!(util::length_to_i32(value.0.len(), offset));
+        let mut byte_size = try!(self.serialize(offset, length));
+        for (k, vs) in value.0 {
+            byte_size += try!(self.serialize(offset + (byte_size as u64), k));
+            byte_size += try!(self.serialize(offset + (byte_size as u64), Cow::from(vs)));
+        }
+        Ok(byte_size)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Lookup<K, V>> {
+        let l: i32 = try!(util::check_non_null(self, offset));
+        let mut m = HashMap::with_capacity(l as usize);
+        for _ in 0..l {
+            let k: K = try!(self.deserialize(offset));
+            let vs: Cow<[V]> = try!(self.deserialize(offset));
+            m.insert(k, vs.into_owned());
+        }
+        Ok(Lookup(m))
+    }
+
+    fn serialized_size(value: &Lookup<K, V>) -> ZeroFormatterResult<i32> {
+        let mut byte_size: i32 = 4;
+        for (k, vs) in &value.0 {
+            byte_size += try!(<R as Formatter<K>>::serialized_size(k));
+            byte_size += try!(groupings_serialized_size::<R, V>(vs));
+        }
+        Ok(byte_size)
+    }
+}
+
+impl<R, K, V> Formatter<Option<Lookup<K, V>>> for R
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>, K: Eq + Hash, V: Clone {
+
+    option_formatter_methods! { Lookup<K, V> }
+}
+
+impl<R, K, V> Formatter<Lazy<Lookup<K, V>>> for R
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>,
+          K: Eq + Hash + HashCode, V: Clone + 'static {
+
+    fn serialize(&mut self, offset: u64, value: Lazy<Lookup<K, V>>) -> ZeroFormatterResult<i32> {
+        let length = try!(util::length_to_i32((value.0).0.len(), offset));
+        let groupings: HashMap<K, Cow<'static, [V]>> = (value.0).0.into_iter()
+            .map(|(k, vs)| (k, Cow::from(vs)))
+            .collect();
+        let mut byte_size: i32 = 4;
+        byte_size += try!(self.serialize(offset + (byte_size as u64), length));
+        byte_size += try!(self.serialize(offset + (byte_size as u64), Lazy(groupings)));
+        try!(self.serialize(offset, byte_size));
+        Ok(byte_size)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Lazy<Lookup<K, V>>> {
+        let start_offset = *offset;
+        let byte_size = try!(util::check_non_null(self, offset));
+        try!(util::check_non_null(self, offset));
+        let groupings: Lazy<HashMap<K, Cow<'static, [V]>>> = try!(self.deserialize(offset));
+        *offset = start_offset + (byte_size as u64);
+        Ok(Lazy(Lookup(groupings.0.into_iter().map(|(k, vs)| (k, vs.into_owned())).collect())))
+    }
+
+    fn serialized_size(value: &Lazy<Lookup<K, V>>) -> ZeroFormatterResult<i32> {
+        let mut keys_and_values: i32 = 0;
+        for (k, vs) in &(value.0).0 {
+            keys_and_values += try!(<R as Formatter<K>>::serialized_size(k));
+            keys_and_values += try!(groupings_serialized_size::<R, V>(vs));
+        }
+        Ok(4 + 4 + dictionary::lazy_dictionary_serialized_size((value.0).0.len() as i32, keys_and_values))
+    }
+}
+
+impl<R, K, V> Formatter<Option<Lazy<Lookup<K, V>>>> for R
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>,
+          K: Eq + Hash + HashCode, V: Clone + 'static {
+
+    option_formatter_methods! { Lazy<Lookup<K, V>> }
+}
+
+/// `LazyLookup` is a lazy view of LazyLookup format.
+/// It looks up a key through the bucket table, and reads only the matching group.
+///
+/// ```
+/// # extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::Cursor;
+/// #
+/// # fn main() {
+/// let mut lookup = Lookup::new();
+/// lookup.insert(1i32, 10i64);
+/// lookup.insert(1i32, 11i64);
+/// lookup.insert(2i32, 20i64);
+/// let mut buffer = Cursor::new(Vec::new());
+/// buffer.serialize(0, Lazy(lookup)).unwrap();
+///
+/// let mut offset = 0;
+/// let mut lookup: LazyLookup<_, i32, i64> = LazyLookup::new(&mut buffer, &mut offset).unwrap();
+/// assert_eq!(lookup.len(), 2);
+/// assert_eq!(lookup.get(&1).unwrap(), vec![10, 11]);
+/// assert_eq!(lookup.get(&3).unwrap(), vec![]);
+/// # }
+/// ```
+pub struct LazyLookup<'a, R: 'a, K, V: Clone + 'static> {
+    length: i32,
+    groupings: LazyDictionary<'a, R, K, Cow<'static, [V]>>
+}
+
+impl<'a, R, K, V> LazyLookup<'a, R, K, V>
+    where R: ByteBuffer + Formatter<K> + Formatter<V> + Formatter<i32>,
+          K: HashCode, V: Clone + 'static {
+
+    /// Read the header at `offset`, and move `offset` to the end of the lookup.
+    pub fn new(buffer: &'a mut R, offset: &mut u64) -> ZeroFormatterResult<LazyLookup<'a, R, K, V>> {
+        let start_offset = *offset;
+        let byte_size = try!(util::check_non_null(buffer, offset));
+        let length = try!(util::check_non_null(buffer, offset));
+        let groupings = try!(LazyDictionary::new(buffer, offset));
+        *offset = start_offset + (byte_size as u64);
+        Ok(LazyLookup { length, groupings })
+    }
+
+    /// Return the number of groups.
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> ZeroFormatterResult<bool> {
+        self.groupings.contains_key(key)
+    }
+
+    /// Read the group of `key`. If `key` is not found, return an empty `Vec`.
+    pub fn get(&mut self, key: &K) -> ZeroFormatterResult<Vec<V>> {
+        self.groupings.get(key).map(|vs| vs.map(|vs| vs.into_owned()).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use std::borrow::Cow;
+    use formatter::*;
+    use dictionary::Lazy;
+    use lookup::*;
+
+    #[test]
+    fn serialize_lookup() {
+        let mut wtr = Cursor::new(Vec::new());
+        let mut input = Lookup::new();
+        input.insert(1i32, 2i16);
+        input.insert(1i32, 3i16);
+        assert_eq!(wtr.serialize(0, input).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![1, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 2, 0, 3, 0]);
+    }
+
+    #[test]
+    fn deserialize_lookup() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 2, 0, 3, 0]);
+        let mut offset = 0;
+        let actual: Lookup<i32, i16> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 16);
+        assert_eq!(actual.get(&1), &[2, 3]);
+        assert_eq!(actual.get(&2), &[]);
+    }
+
+    #[test]
+    fn serialize_lookup_none() {
+        let mut wtr = Cursor::new(Vec::new());
+        let input: Option<Lookup<i32, i32>> = None;
+        assert_eq!(wtr.serialize(0, input).unwrap(), 4);
+        assert_eq!(wtr.into_inner(), vec![0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn serialize_deserialize_lazy_lookup() {
+        let mut c = Cursor::new(Vec::new());
+        let mut input = Lookup::new();
+        input.insert(Cow::Borrowed("a"), 1u8);
+        input.insert(Cow::Borrowed("b"), 2u8);
+        input.insert(Cow::Borrowed("a"), 3u8);
+        c.serialize(0, Lazy(input.clone())).unwrap();
+        let mut offset = 0;
+        let actual: Lazy<Lookup<Cow<'static, str>, u8>> = c.deserialize(&mut offset).unwrap();
+        assert_eq!(Lazy(input), actual);
+    }
+
+    #[test]
+    fn lazy_lookup_get() {
+        let mut c = Cursor::new(Vec::new());
+        let mut input = Lookup::new();
+        for i in 0..50i32 {
+            input.insert(i % 10, Cow::Owned::<'static, str>(i.to_string()));
+        }
+        let byte_size = c.serialize(0, Lazy(input)).unwrap();
+        let mut offset = 0;
+        let mut lookup: LazyLookup<_, i32, Cow<'static, str>> = LazyLookup::new(&mut c, &mut offset).unwrap();
+        assert_eq!(offset, byte_size as u64);
+        assert_eq!(lookup.len(), 10);
+        let expected: Vec<Cow<'static, str>> = vec![Cow::Borrowed("3"), Cow::Borrowed("13"), Cow::Borrowed("23"), Cow::Borrowed("33"), Cow::Borrowed("43")];
+        assert_eq!(lookup.get(&3).unwrap(), expected);
+        assert!(lookup.get(&10).unwrap().is_empty());
+        assert!(!lookup.contains_key(&10).unwrap());
+    }
+
+    #[test]
+    fn serialized_size_lookup() {
+        let mut lookup = Lookup::new();
+        lookup.insert(1i32, 10i64);
+        lookup.insert(1i32, 11i64);
+        lookup.insert(2i32, 20i64);
+        let mut wtr = Cursor::new(Vec::new());
+        let expected = wtr.serialize(0, lookup.clone()).unwrap();
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Lookup<i32, i64>>>::serialized_size(&lookup).unwrap(), expected);
+        let input = Lazy(lookup);
+        let expected = wtr.serialize(0, input.clone()).unwrap();
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Lazy<Lookup<i32, i64>>>>::serialized_size(&input).unwrap(), expected);
+    }
+}
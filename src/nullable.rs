@@ -0,0 +1,46 @@
+//! Serialize `Option` as -1 length format instead of has value format.
+//!
+//! Use it for the types whose `null` is written as -1 length or byte size (`String`, `Sequence<T>`, Object and Union).
+//!
+//! ```
+//! # extern crate serde;
+//! # #[macro_use] extern crate serde_derive;
+//! # extern crate zero_formatter;
+//! #[derive(Serialize, Deserialize)]
+//! struct Sample {
+//!     #[serde(with = "zero_formatter::nullable")]
+//!     name: Option<String>
+//! }
+//! # fn main() {}
+//! ```
+
+use ser::NULLABLE;
+
+use std::fmt;
+use std::marker::PhantomData;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Visitor;
+
+pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize, S: Serializer {
+    serializer.serialize_newtype_struct(NULLABLE, value)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where T: Deserialize<'de>, D: Deserializer<'de> {
+    deserializer.deserialize_newtype_struct(NULLABLE, NullableVisitor(PhantomData))
+}
+
+struct NullableVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for NullableVisitor<T> where T: Deserialize<'de> {
+    type Value = Option<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("nullable value")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Option<T>, D::Error> where D: Deserializer<'de> {
+        Option::deserialize(deserializer)
+    }
+}
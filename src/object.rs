@@ -1,18 +1,16 @@
 use error::*;
 use formatter::*;
 
-use std::io::Seek;
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use dictionary::HashCode;
+use buffer::ByteBuffer;
 
 /// `struct_formatter` define sturct type and provide sequential fields formatter.
 /// But, `struct_formatter` does not support [versioning](https://github.com/neuecc/ZeroFormatter/tree/1.6.0#versioning).
 ///
 /// ```
 /// # #[macro_use] extern crate zero_formatter;
-/// # extern crate byteorder;
 /// # use zero_formatter::*;
-/// # use std::io::{Seek, SeekFrom, Read, Write, Cursor, Result};
-/// # use byteorder::{ReadBytesExt, WriteBytesExt};
+/// # use std::io::{Cursor, Result};
 /// #
 /// # declare_buffer! { Buffer }
 /// #
@@ -61,11 +59,31 @@ macro_rules! struct_formatter {
             fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<$name> {
 
                 $(
-                let $field_name: $field_type = try!(self.deserialize(offset));
+                let $field_name: $field_type = try!(self.deserialize(offset)
+                    .map_err(|e| e.in_field(stringify!($name), stringify!($field_name))));
                 )*
 
                 Ok($name { $($field_name: $field_name),* })
             }
+
+            fn serialized_size(value: &$name) -> ZeroFormatterResult<i32> {
+                let mut byte_size: i32 = 0;
+                $(
+                byte_size += try!(<Self as Formatter<$field_type>>::serialized_size(&value.$field_name));
+                )*
+                Ok(byte_size)
+            }
+
+            fn length() -> Option<i32> {
+                let mut l: i32 = 0;
+                $(
+                l += match <Self as Formatter<$field_type>>::length() {
+                    Some(n) => n,
+                    None => return None
+                };
+                )*
+                Some(l)
+            }
         }
 
         has_value_formatter! {
@@ -75,62 +93,126 @@ macro_rules! struct_formatter {
     }
 }
 
-impl<R, A1, A2> Formatter<(A1, A2)> for R
-  where R: Seek + ReadBytesExt + WriteBytesExt + Formatter<A1> + Formatter<A2> {
+macro_rules! tuple_formatter_impl {
+    ($($a:ident: $v:ident: $i:tt),*) => {
+        impl<R, $($a),*> Formatter<($($a,)*)> for R
+            where R: ByteBuffer $(+ Formatter<$a>)* {
 
-    fn serialize(&mut self, offset: u64, value: (A1, A2)) -> ZeroFormatterResult<i32> {
-        let r1 = try!(self.serialize(offset, value.0));
-        let r2 = try!(self.serialize(offset + (r1 as u64), value.1));
-        Ok(r1 + r2)
-    }
+            fn serialize(&mut self, offset: u64, value: ($($a,)*)) -> ZeroFormatterResult<i32> {
+                let mut byte_size: i32 = 0;
+                $(
+                byte_size += try!(self.serialize(offset + (byte_size as u64), value.$i));
+                )*
+                Ok(byte_size)
+            }
 
-    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<(A1, A2)> {
-        let a1: A1 = try!(self.deserialize(offset));
-        let a2: A2 = try!(self.deserialize(offset));
-        Ok((a1, a2))
-    }
-}
+            fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<($($a,)*)> {
+                $(
+                let $v: $a = try!(self.deserialize(offset));
+                )*
+                Ok(($($v,)*))
+            }
+
+            fn serialized_size(value: &($($a,)*)) -> ZeroFormatterResult<i32> {
+                let mut byte_size: i32 = 0;
+                $(
+                byte_size += try!(<Self as Formatter<$a>>::serialized_size(&value.$i));
+                )*
+                Ok(byte_size)
+            }
 
-impl<R, A1, A2> Formatter<Option<(A1, A2)>> for R
-    where R: Seek + ReadBytesExt + WriteBytesExt + Formatter<A1> + Formatter<A2> {
-
-    fn serialize(&mut self, offset: u64, value: Option<(A1, A2)>) -> ZeroFormatterResult<i32> {
-        match value {
-            None => {
-                self.serialize(offset, false)
-            },
-            Some((v1, v2)) => {
-                let r0 = try!(self.serialize(offset, true));
-                let r1 = try!(self.serialize(offset + (r0 as u64), v1));
-                let r2 = try!(self.serialize(offset + ((r0 + r1) as u64), v2));
-                Ok(r0 + r1 + r2)
+            fn length() -> Option<i32> {
+                let mut l: i32 = 0;
+                $(
+                l += match <Self as Formatter<$a>>::length() {
+                    Some(n) => n,
+                    None => return None
+                };
+                )*
+                Some(l)
             }
         }
-    }
 
-    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Option<(A1, A2)>> {
+        impl<R, $($a),*> Formatter<Option<($($a,)*)>> for R
+            where R: ByteBuffer $(+ Formatter<$a>)* {
 
-        let has_value: bool = try!(self.deserialize(offset));
-        if has_value {
-            let a1: A1 = try!(self.deserialize(offset));
-            let a2: A2 = try!(self.deserialize(offset));
-            Ok(Some((a1, a2)))
+            has_value_formatter_methods! { ($($a,)*) }
         }
-        else {
-            Ok(None)
+
+        impl<$($a: HashCode),*> HashCode for KeyTuple<($($a,)*)> {
+            fn hash_code(&self) -> i32 {
+                let hs = [$((self.0).$i.hash_code()),*];
+                hs[1..].iter().fold(hs[0], |h, x| (h.wrapping_shl(5).wrapping_add(h)) ^ x)
+            }
         }
     }
 }
 
+tuple_formatter_impl! { A1: a1: 0 }
+tuple_formatter_impl! { A1: a1: 0, A2: a2: 1 }
+tuple_formatter_impl! { A1: a1: 0, A2: a2: 1, A3: a3: 2 }
+tuple_formatter_impl! { A1: a1: 0, A2: a2: 1, A3: a3: 2, A4: a4: 3 }
+tuple_formatter_impl! { A1: a1: 0, A2: a2: 1, A3: a3: 2, A4: a4: 3, A5: a5: 4 }
+tuple_formatter_impl! { A1: a1: 0, A2: a2: 1, A3: a3: 2, A4: a4: 3, A5: a5: 4, A6: a6: 5 }
+tuple_formatter_impl! { A1: a1: 0, A2: a2: 1, A3: a3: 2, A4: a4: 3, A5: a5: 4, A6: a6: 5, A7: a7: 6 }
+tuple_formatter_impl! { A1: a1: 0, A2: a2: 1, A3: a3: 2, A4: a4: 3, A5: a5: 4, A6: a6: 5, A7: a7: 6, A8: a8: 7 }
+
+/// `KeyTuple` wraps a tuple of arity 1 to 8 to use it as a dictionary key, like `KeyTuple` of ZeroFormatter.
+/// The layout is the same as the tuple, `[Item1:T1, Item2:T2, ...]`.
+/// `HashCode` combines hash codes of the items in the same way as `Tuple` of .NET.
+///
+/// ```
+/// # extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::Cursor;
+/// # use std::collections::HashMap;
+/// #
+/// # fn main() {
+/// let mut m = HashMap::new();
+/// m.insert(KeyTuple((1i32, 2i64)), 3i32);
+/// let mut buffer = Cursor::new(Vec::new());
+/// buffer.serialize(0, m.clone()).unwrap();
+/// let mut offset = 0;
+/// let actual: HashMap<KeyTuple<(i32, i64)>, i32> = buffer.deserialize(&mut offset).unwrap();
+/// assert_eq!(m, actual);
+/// # }
+/// ```
+#[derive(Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct KeyTuple<T>(pub T);
+
+impl<R, T> Formatter<KeyTuple<T>> for R
+    where R: ByteBuffer + Formatter<T> {
+
+    fn serialize(&mut self, offset: u64, value: KeyTuple<T>) -> ZeroFormatterResult<i32> {
+        self.serialize(offset, value.0)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<KeyTuple<T>> {
+        self.deserialize(offset).map(KeyTuple)
+    }
+
+    fn length() -> Option<i32> {
+        <Self as Formatter<T>>::length()
+    }
+
+    fn serialized_size(value: &KeyTuple<T>) -> ZeroFormatterResult<i32> {
+        <Self as Formatter<T>>::serialized_size(&value.0)
+    }
+}
+
+impl<R, T> Formatter<Option<KeyTuple<T>>> for R
+    where R: ByteBuffer + Formatter<T> {
+
+    has_value_formatter_methods! { KeyTuple<T> }
+}
+
 /// `object_formatter` define struct type and provide formatter.
 /// `object_formatter` support [versioning](https://github.com/neuecc/ZeroFormatter/tree/1.6.0#versioning).
 ///
 /// ```
 /// # #[macro_use] extern crate zero_formatter;
-/// # extern crate byteorder;
 /// # use zero_formatter::*;
-/// # use std::io::{Seek, SeekFrom, Read, Write, Cursor, Result};
-/// # use byteorder::{ReadBytesExt, WriteBytesExt};
+/// # use std::io::{Cursor, Result};
 /// #
 /// # declare_buffer! { Buffer }
 /// #
@@ -152,8 +234,29 @@ impl<R, A1, A2> Formatter<Option<(A1, A2)>> for R
 /// # example();
 /// # }
 /// ```
+///
+/// `#[view(Name)]` also defines a lazy view. See `object_view`.
 #[macro_export]
 macro_rules! object_formatter {
+    (#[target($buffer:ty)]
+    #[view($view:ident)]
+    $name:ident {
+        $($index:expr; $field_name:ident: $field_type:ty),*
+    }) => {
+        object_formatter! {
+            #[target($buffer)]
+            $name {
+                $($index; $field_name: $field_type),*
+            }
+        }
+
+        object_view! {
+            #[target($buffer)]
+            $view {
+                $($index; $field_name: $field_type),*
+            }
+        }
+    };
     (#[target($buffer:ty)]
     $name:ident {
         $($index:expr; $field_name:ident: $field_type:ty),*
@@ -163,6 +266,63 @@ macro_rules! object_formatter {
             $(pub $field_name: $field_type),*
         }
 
+        object_formatter_impl! {
+            #[target($buffer)]
+            $name {
+                $($index; $field_name: $field_type),*
+            }
+        }
+
+        tracked_formatter! {
+            #[target($buffer)]
+            $name {
+                $($index; $field_name: $field_type),*
+            }
+        }
+    }
+}
+
+/// `object_formatter_impl` provide formatter for an existing struct type.
+/// It is used by `object_formatter` and `#[derive(ZeroFormatter)]`.
+/// The struct must implement `Default` because missing fields are filled by default values.
+///
+/// ```
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::{Cursor, Result};
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// #[derive(Default, Debug, PartialEq)]
+/// pub struct ObjectSample {
+///     a: i32,
+///     b: i64
+/// }
+///
+/// object_formatter_impl! {
+///     #[target(Buffer<Cursor<Vec<u8>>>)]
+///     ObjectSample {
+///         0; a: i32,
+///         1; b: i64
+///     }
+/// }
+///
+/// # fn example() -> Result<()> {
+/// # let mut writer = Buffer::new(Cursor::new(Vec::new()));
+/// try!(writer.serialize(0, ObjectSample { a: 1, b: 2 }));
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// # example();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! object_formatter_impl {
+    (#[target($buffer:ty)]
+    $name:ident {
+        $($index:expr; $field_name:ident: $field_type:ty),*
+    }) => {
         impl Formatter<$name> for $buffer {
 
             fn serialize(&mut self, offset: u64, value: $name) -> ZeroFormatterResult<i32> {
@@ -178,7 +338,6 @@ macro_rules! object_formatter {
                 )*
 
                 try!(self.serialize(offset, byte_size));
-                try!(self.seek(SeekFrom::Start(offset + (byte_size as u64))));
                 Ok(byte_size)
             }
 
@@ -189,23 +348,22 @@ macro_rules! object_formatter {
                 let last_index: i32 = try!(self.deserialize(offset));
 
                 $(
-                let $field_name: $field_type = try!(if $index <= last_index {
-                    *offset = start_offset + 4 + 4 + 4 * $index;
-                    let o: i32 = try!(self.deserialize(offset));
-                    if o == 0 {
-                        Ok(Default::default())
-                    } else {
-                        *offset = o as u64;
-                        self.deserialize(offset)
-                    }
-                } else {
-                    Ok(Default::default())
-                });
+                let $field_name: $field_type = try!(util::deserialize_object_field(self, start_offset, last_index, $index)
+                    .map_err(|e| e.in_field(stringify!($name), stringify!($field_name))));
                 )*
 
                 *offset = start_offset + (byte_size as u64);
                 Ok($name { $($field_name: $field_name),* })
             }
+
+            fn serialized_size(value: &$name) -> ZeroFormatterResult<i32> {
+                let last_index: i32 = *([$($index),*].iter().max().unwrap());
+                let mut byte_size: i32 = 4 + 4 + 4 * (last_index + 1);
+                $(
+                byte_size += try!(<Self as Formatter<$field_type>>::serialized_size(&value.$field_name));
+                )*
+                Ok(byte_size)
+            }
         }
 
         option_formatter! {
@@ -215,14 +373,114 @@ macro_rules! object_formatter {
     }
 }
 
+/// `object_view` define a lazy view of Object format over a byte slice.
+/// The view reads the header only, and each accessor reads the field through the offset table on demand.
+/// Like `deserialize`, a field which is not written returns the default value.
+/// The target must be a `MemoryBuffer`, such as a buffer declared over `VecBuffer<'_>`, so the view reads the slice without copying it.
+/// A field cannot be named `new`, because it is the constructor of the view.
+///
+/// ```
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::Result;
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// object_formatter! {
+///     #[target(Buffer<VecBuffer<'_>>)]
+///     #[view(ObjectSampleView)]
+///     ObjectSample {
+///         0; a: i32,
+///         1; b: i64
+///     }
+/// }
+///
+/// # fn example() -> Result<()> {
+/// let bytes = try!(to_vec::<Buffer<VecBuffer>, _>(ObjectSample { a: 1, b: 2 }));
+/// let mut offset = 0;
+/// let view = try!(ObjectSampleView::new(&bytes, &mut offset));
+/// assert_eq!(try!(view.b()), 2);
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// # example().unwrap();
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// object_formatter! {
+///     #[target(Buffer<VecBuffer<'_>>)]
+///     #[view(ObjectSampleView)]
+///     ObjectSample {
+///         0; new: i32
+///     }
+/// }
+/// #
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! object_view {
+    (@field new) => {
+        compile_error!("`new` cannot be a field name of object_view, because it is the constructor of the view");
+    };
+    (@field $field_name:ident) => {};
+    (#[target($buffer:ty)]
+    $view:ident {
+        $($index:expr; $field_name:ident: $field_type:ty),*
+    }) => {
+        $(object_view! { @field $field_name })*
+
+        pub struct $view<'a> {
+            bytes: &'a [u8],
+            offset: u64,
+            last_index: i32
+        }
+
+        #[allow(dead_code)]
+        impl<'a> $view<'a> {
+
+            /// Read the header at `offset` of `bytes`, and move `offset` to the end of the object.
+            pub fn new(bytes: &'a [u8], offset: &mut u64) -> ZeroFormatterResult<$view<'a>> {
+                let mut buffer: $buffer = $crate::MemoryBuffer::from_slice(bytes);
+                let start_offset: u64 = *offset;
+                let byte_size = try!(util::check_non_null(&mut buffer, offset));
+                let last_index: i32 = try!(buffer.deserialize(offset));
+                *offset = start_offset + (byte_size as u64);
+                Ok($view { bytes: bytes, offset: start_offset, last_index: last_index })
+            }
+
+            $(
+            pub fn $field_name(&self) -> ZeroFormatterResult<$field_type> {
+                let mut buffer: $buffer = $crate::MemoryBuffer::from_slice(self.bytes);
+                match try!(util::object_field_offset(&mut buffer, self.offset, self.last_index, $index)) {
+                    Some(mut o) => buffer.deserialize(&mut o),
+                    None => Ok(Default::default())
+                }
+            }
+            )*
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use std::io::Cursor;
-    use std::io::{Seek, SeekFrom};
+    use std::borrow::Cow;
+    use std::collections::HashMap;
     use error::*;
     use formatter::*;
     use util;
+    use dictionary::{Lazy, HashCode, LazyDictionary};
+    use object::KeyTuple;
+    use tracked::Tracked;
+    use vec_buffer::VecBuffer;
 
     object_formatter! {
         #[target(Cursor<Vec<u8>>)]
@@ -292,6 +550,126 @@ mod tests {
         assert_eq!(O2 { a: 1, b: 2, c: 0 }, rdr.deserialize(&mut offset).unwrap());
     }
 
+    #[derive(Default, Debug, PartialEq)]
+    pub struct OV {
+        a: i32,
+        b: Cow<'static, str>,
+        c: i8
+    }
+
+    object_formatter_impl! {
+        #[target(VecBuffer<'_>)]
+        OV {
+            0; a: i32,
+            1; b: Cow<'static, str>,
+            3; c: i8
+        }
+    }
+
+    object_view! {
+        #[target(VecBuffer<'_>)]
+        OVView {
+            0; a: i32,
+            1; b: Cow<'static, str>,
+            3; c: i8
+        }
+    }
+
+    object_formatter! {
+        #[target(VecBuffer<'_>)]
+        #[view(O3View)]
+        O3 {
+            0; a: i32,
+            1; b: i64
+        }
+    }
+
+    #[test]
+    fn object_view() {
+        let mut c = VecBuffer::default();
+        c.serialize(0, 0xffi32).unwrap();
+        let byte_size = c.serialize(4, OV { a: 1, b: Cow::Borrowed("abc"), c: 2 }).unwrap();
+        let bytes = c.into_inner();
+        let mut offset = 4;
+        let view = OVView::new(&bytes, &mut offset).unwrap();
+        assert_eq!(offset, 4 + byte_size as u64);
+        assert_eq!(view.c().unwrap(), 2);
+        assert_eq!(view.b().unwrap(), Cow::Borrowed("abc"));
+        assert_eq!(view.a().unwrap(), 1);
+    }
+
+    #[test]
+    fn object_view_versioning() {
+        let bytes = [28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0];
+        let mut offset = 0;
+        let view = OVView::new(&bytes, &mut offset).unwrap();
+        assert_eq!(view.a().unwrap(), 1);
+        assert_eq!(view.c().unwrap(), 0);
+    }
+
+    #[test]
+    fn patch_object_field_slice() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, O2 { a: 1, b: 2, c: 3 }).unwrap();
+        let mut bytes = c.into_inner();
+        util::patch_object_field(&mut Cursor::new(&mut bytes[..]), 0, 1, 5i64).unwrap();
+        let mut offset = 0;
+        let actual: O2 = Cursor::new(bytes).deserialize(&mut offset).unwrap();
+        assert_eq!(O2 { a: 1, b: 5, c: 3 }, actual);
+    }
+
+    #[test]
+    fn patch_object_field_size_mismatch() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, O2 { a: 1, b: 2, c: 3 }).unwrap();
+        let e = util::patch_object_field(&mut c, 0, 1, 5i32).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::SizeMismatch { expected: 8, actual: 4 }));
+        assert_eq!(e.offset(), Some(24));
+        let e = util::patch_object_field(&mut c, 0, 2, 5i64).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::SizeMismatch { expected: 1, actual: 8 }));
+        let e = util::patch_object_field(&mut c, 0, 3, 5i8).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::MissingField(3)));
+        let e = util::patch_object_field(&mut c, 0, 0, Cow::Borrowed("a")).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::NotFixedLength));
+    }
+
+    #[test]
+    fn patch_object_field_out_of_range() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, O { a: 1, b: 2 }).unwrap();
+        let bytes = c.into_inner();
+
+        let mut field_after_end = bytes.clone();
+        field_after_end[12] = 40;
+        let e = util::patch_object_field(&mut Cursor::new(field_after_end), 0, 1, 5i64).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::OffsetOutOfRange(40)));
+        assert_eq!(e.offset(), Some(12));
+
+        let mut field_in_header = bytes.clone();
+        field_in_header[12] = 8;
+        let e = util::patch_object_field(&mut Cursor::new(field_in_header), 0, 1, 5i64).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::OffsetOutOfRange(8)));
+
+        let mut index_after_end = bytes.clone();
+        index_after_end[4] = 10;
+        let e = util::patch_object_field(&mut Cursor::new(index_after_end), 0, 1, 5i64).unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::OffsetOutOfRange(10)));
+        assert_eq!(e.offset(), Some(4));
+    }
+
+    #[test]
+    fn object_formatter_view() {
+        let mut c = VecBuffer::default();
+        c.serialize(0, O3 { a: 1, b: 2 }).unwrap();
+        util::patch_object_field(&mut c, 0, 0, 10i32).unwrap();
+        let bytes = c.into_inner();
+        let mut offset = 0;
+        let view = O3View::new(&bytes, &mut offset).unwrap();
+        assert_eq!(view.a().unwrap(), 10);
+        assert_eq!(view.b().unwrap(), 2);
+        assert!(O3View::new(&bytes[..8], &mut 0).unwrap().b().is_err());
+    }
+
     struct_formatter! {
         #[target(Cursor<Vec<u8>>)]
         S {
@@ -332,8 +710,8 @@ mod tests {
     fn serialize_struct_none() {
         let mut wtr = Cursor::new(Vec::new());
         let input: Option<S> = None;
-        assert_eq!(wtr.serialize(0, input).unwrap(), 1);
-        assert_eq!(wtr.into_inner(), vec![0]);
+        assert_eq!(wtr.serialize(0, input).unwrap(), 13);
+        assert_eq!(wtr.into_inner(), vec![0; 13]);
     }
 
     #[test]
@@ -371,4 +749,260 @@ mod tests {
         let mut offset = 0;
         assert_eq!(Some((1u8, 2u8)), rdr.deserialize(&mut offset).unwrap());
     }
+
+    #[test]
+    fn serialize_1_tuple() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, (1i32,)).unwrap(), 4);
+        assert_eq!(wtr.into_inner(), vec![1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn deserialize_1_tuple() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!((1i32,), rdr.deserialize(&mut offset).unwrap());
+    }
+
+    #[test]
+    fn serialize_8_tuple() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, (1u8, 2i16, 3u8, 4u8, 5u8, 6u8, 7u8, 8u8)).unwrap(), 9);
+        assert_eq!(wtr.into_inner(), vec![1, 2, 0, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn deserialize_8_tuple() {
+        let mut rdr = Cursor::new(vec![1, 2, 0, 3, 4, 5, 6, 7, 8]);
+        let mut offset = 0;
+        assert_eq!((1u8, 2i16, 3u8, 4u8, 5u8, 6u8, 7u8, 8u8), rdr.deserialize(&mut offset).unwrap());
+        assert_eq!(offset, 9);
+    }
+
+    #[test]
+    fn serialize_some_3_tuple() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, Some((1u8, 2u8, 3u8))).unwrap(), 4);
+        assert_eq!(wtr.into_inner(), vec![1, 1, 2, 3]);
+    }
+
+    #[test]
+    fn deserialize_none_3_tuple() {
+        let mut rdr = Cursor::new(vec![0]);
+        let mut offset = 0;
+        let expected: Option<(u8, u8, u8)> = None;
+        assert_eq!(expected, rdr.deserialize(&mut offset).unwrap());
+    }
+
+    #[test]
+    fn tuple_length() {
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<(u8, i32, i64)>>::length(), Some(13));
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Option<(u8, i32, i64)>>>::length(), Some(14));
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<(u8, Cow<'static, str>)>>::length(), None);
+    }
+
+    #[test]
+    fn serialize_key_tuple() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, KeyTuple((1u8, 2u8, 3u8))).unwrap(), 3);
+        assert_eq!(wtr.into_inner(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn deserialize_key_tuple_some() {
+        let mut rdr = Cursor::new(vec![1, 1, 2, 3]);
+        let mut offset = 0;
+        assert_eq!(Some(KeyTuple((1u8, 2u8, 3u8))), rdr.deserialize(&mut offset).unwrap());
+    }
+
+    #[test]
+    fn key_tuple_hash_code() {
+        assert_eq!(KeyTuple((7i32,)).hash_code(), 7);
+        assert_eq!(KeyTuple((1i32, 2i32)).hash_code(), ((1 << 5) + 1) ^ 2);
+    }
+
+    #[test]
+    fn key_tuple_lazy_dictionary_key() {
+        let mut c = Cursor::new(Vec::new());
+        let mut input = HashMap::new();
+        input.insert(KeyTuple((1i32, 2i32)), 3i32);
+        input.insert(KeyTuple((2i32, 1i32)), 4i32);
+        c.serialize(0, Lazy(input)).unwrap();
+        let mut offset = 0;
+        let mut d: LazyDictionary<_, KeyTuple<(i32, i32)>, i32> = LazyDictionary::new(&mut c, &mut offset).unwrap();
+        assert_eq!(d.get(&KeyTuple((2, 1))).unwrap(), Some(4));
+        assert_eq!(d.get(&KeyTuple((1, 1))).unwrap(), None);
+    }
+
+    object_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        TO {
+            0; a: i32,
+            1; b: i64
+        }
+    }
+
+    #[derive(Default, Debug, PartialEq, Clone)]
+    pub struct TV {
+        a: Cow<'static, str>,
+        b: i32
+    }
+
+    object_formatter_impl! {
+        #[target(Cursor<Vec<u8>>)]
+        TV {
+            0; a: Cow<'static, str>,
+            1; b: i32
+        }
+    }
+
+    tracked_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        TV {
+            0; a: Cow<'static, str>,
+            1; b: i32
+        }
+    }
+
+    #[test]
+    fn serialize_tracked_unchanged_field() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, TO { a: 1, b: 2 }).unwrap();
+        let mut offset = 0;
+        let mut tracked: Tracked<TO> = c.deserialize(&mut offset).unwrap();
+        assert!(tracked.is_tracked());
+        assert_eq!(*tracked, TO { a: 1, b: 2 });
+        tracked.b = 3;
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, tracked).unwrap(), 28);
+        let mut offset = 0;
+        let actual: TO = wtr.deserialize(&mut offset).unwrap();
+        assert_eq!(TO { a: 1, b: 3 }, actual);
+    }
+
+    #[test]
+    fn serialize_tracked_copies_original_bytes() {
+        // the original bytes of `a` is 9, so `a` is copied rather than serialized.
+        let bytes = vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 9, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0];
+        let mut tracked = Tracked::from_origin(TO { a: 1, b: 2 }, TO { a: 1, b: 2 }, 0, bytes);
+        tracked.b = 3;
+        let mut wtr = Cursor::new(Vec::new());
+        wtr.serialize(0, tracked).unwrap();
+        let mut offset = 0;
+        let actual: TO = wtr.deserialize(&mut offset).unwrap();
+        assert_eq!(TO { a: 9, b: 3 }, actual);
+    }
+
+    #[test]
+    fn serialize_tracked_invalid_original_bytes() {
+        // lastIndex 10 does not fit in byteSize 28, and the offset 40 of `b` is after the end.
+        let invalid = vec![
+            vec![28, 0, 0, 0, 10, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 9, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0],
+            vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 40, 0, 0, 0, 9, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0],
+            vec![28, 0, 0, 0]
+        ];
+        for bytes in invalid {
+            let tracked = Tracked::from_origin(TO { a: 1, b: 2 }, TO { a: 1, b: 2 }, 0, bytes);
+            let mut wtr = Cursor::new(Vec::new());
+            assert_eq!(wtr.serialize(0, tracked).unwrap(), 28);
+            let mut offset = 0;
+            let actual: TO = wtr.deserialize(&mut offset).unwrap();
+            assert_eq!(TO { a: 1, b: 2 }, actual);
+        }
+    }
+
+    #[test]
+    fn serialize_tracked_moved_fixed_field() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, TO { a: 1, b: 2 }).unwrap();
+        let mut offset = 0;
+        let tracked: Tracked<TO> = c.deserialize(&mut offset).unwrap();
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(4, tracked).unwrap(), 28);
+        let mut offset = 4;
+        let actual: TO = wtr.deserialize(&mut offset).unwrap();
+        assert_eq!(TO { a: 1, b: 2 }, actual);
+    }
+
+    #[test]
+    fn serialize_tracked_variable_length_field() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, TV { a: Cow::Borrowed("abc"), b: 2 }).unwrap();
+        let mut offset = 0;
+        let mut tracked: Tracked<TV> = c.deserialize(&mut offset).unwrap();
+        tracked.a = Cow::Borrowed("abcdef");
+        let byte_size = c.serialize(0, tracked).unwrap();
+        assert_eq!(byte_size, 8 + 8 + 10 + 4);
+        let mut offset = 0;
+        let actual: TV = c.deserialize(&mut offset).unwrap();
+        assert_eq!(TV { a: Cow::Borrowed("abcdef"), b: 2 }, actual);
+    }
+
+    #[test]
+    fn serialize_untracked() {
+        let mut c = Cursor::new(Vec::new());
+        assert_eq!(c.serialize(0, Tracked::new(TO { a: 1, b: 2 })).unwrap(), 28);
+        let mut offset = 0;
+        let actual: TO = c.deserialize(&mut offset).unwrap();
+        assert_eq!(TO { a: 1, b: 2 }, actual);
+    }
+
+    #[test]
+    fn serialized_size_object() {
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<O>>::serialized_size(&O { a: 1, b: 2 }).unwrap(), 28);
+        let input = OV { a: 1, b: Cow::Borrowed("abc"), c: 2 };
+        let mut wtr = VecBuffer::default();
+        let expected = wtr.serialize(0, OV { a: 1, b: Cow::Borrowed("abc"), c: 2 }).unwrap();
+        assert_eq!(<VecBuffer as Formatter<OV>>::serialized_size(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn serialized_size_struct_and_tuple() {
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<S>>::serialized_size(&S { a: 1, b: 2 }).unwrap(), 12);
+        let input = (1i32, Cow::Borrowed("abc"));
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<(i32, Cow<str>)>>::serialized_size(&input).unwrap(), 11);
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Option<(i32, Cow<str>)>>>::serialized_size(&Some(input)).unwrap(), 12);
+    }
+
+    #[test]
+    fn serialized_size_tracked() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, TV { a: Cow::Borrowed("abc"), b: 2 }).unwrap();
+        let mut offset = 0;
+        let mut tracked: Tracked<TV> = c.deserialize(&mut offset).unwrap();
+        tracked.a = Cow::Borrowed("abcdef");
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Tracked<TV>>>::serialized_size(&tracked).unwrap(), 8 + 8 + 10 + 4);
+    }
+
+    object_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        Item {
+            0; price: bool
+        }
+    }
+
+    #[derive(Default, Debug, PartialEq)]
+    pub struct Order {
+        items: Vec<Item>
+    }
+
+    object_formatter_impl! {
+        #[target(Cursor<Vec<u8>>)]
+        Order {
+            0; items: Vec<Item>
+        }
+    }
+
+    #[test]
+    fn deserialize_object_error_path() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, Order { items: vec![Item { price: true }, Item { price: false }] }).unwrap();
+        c.get_mut()[53] = 2;
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<Order> = c.deserialize(&mut offset);
+        let e = actual.unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::InvalidBool(2)));
+        assert_eq!(e.offset(), Some(53));
+        assert_eq!(e.to_string(), "Order.items[1].price: [offset 53] 2 is not a bool byte");
+    }
 }
@@ -1,35 +1,47 @@
+#[macro_export]
+macro_rules! option_formatter_methods {
+    ($t:ty) => (
+        fn serialize(&mut self, offset: u64, value: Option<$t>) -> ZeroFormatterResult<i32> {
+            match value {
+                None => {
+                    <Self as Formatter<i32>>::serialize(self, offset, -1i32)
+                },
+                Some(v) => {
+                    <Self as Formatter<$t>>::serialize(self, offset, v)
+                }
+            }
+        }
+
+        fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Option<$t>> {
+            let len: i32 = try!(<Self as Formatter<i32>>::deserialize(self, offset));
+            if len == -1 {
+                Ok(None)
+            }
+            else if len < -1 {
+                ZeroFormatterError::invalid($crate::ErrorKind::NegativeLength(len), *offset - 4)
+            }
+            else {
+                *offset -= 4;
+                <Self as Formatter<$t>>::deserialize(self, offset).map(|v| Some(v))
+            }
+        }
+
+        fn serialized_size(value: &Option<$t>) -> ZeroFormatterResult<i32> {
+            match *value {
+                None => Ok(4),
+                Some(ref v) => <Self as Formatter<$t>>::serialized_size(v)
+            }
+        }
+    )
+}
+
 #[macro_export]
 macro_rules! option_formatter {
     (#[target($buffer:ty)]
     $name:ident
     ) => (
         impl Formatter<Option<$name>> for $buffer {
-
-            fn serialize(&mut self, offset: u64, value: Option<$name>) -> ZeroFormatterResult<i32> {
-                try!(self.seek(SeekFrom::Start(offset)));
-                match value {
-                    None => {
-                        self.serialize(offset, -1i32)
-                    },
-                    Some(v) => {
-                        self.serialize(offset, v)
-                    }
-                }
-            }
-
-            fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<Option<$name>> {
-                let len: i32 = try!(self.deserialize(offset));
-                if len == -1 {
-                    Ok(None)
-                }
-                else if len < -1 {
-                    ZeroFormatterError::invalid_binary(*offset)
-                }
-                else {
-                    *offset -= 4;
-                    self.deserialize(offset).map(|v| Some(v))
-                }
-            }
+            option_formatter_methods! { $name }
         }
     )
 }
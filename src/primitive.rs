@@ -1,32 +1,35 @@
 use error::*;
 use formatter::*;
 use util;
+use buffer::ByteBuffer;
 
 use std::{i32, usize};
 use std::borrow::Cow;
 use std::ops::Deref;
 use std::string::String;
 //use std::convert::TryFrom;
-use std::io::{Seek, SeekFrom};
-use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+use byteorder::{ByteOrder, LittleEndian};
 
-impl<R> Formatter<u8> for R where R: Seek + ReadBytesExt + WriteBytesExt {
+impl<R> Formatter<u8> for R where R: ByteBuffer {
 
     fn serialize(&mut self, offset: u64, value: u8) -> ZeroFormatterResult<i32> {
-        try!(self.seek(SeekFrom::Start(offset)));
-        try!(self.write_u8(value));
+        try!(self.write_at(offset, &[value]));
         Ok(1)
     }
 
     fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<u8> {
-        try!(self.seek(SeekFrom::Start(*(offset as &u64))));
-        let n = try!(self.read_u8());
+        let mut buf = [0; 1];
+        try!(self.read_at(*offset, &mut buf));
         *offset += 1;
-        Ok(n)
+        Ok(buf[0])
+    }
+
+    fn length() -> Option<i32> {
+        Some(1)
     }
 }
 
-impl<R> Formatter<bool> for R where R: Seek + ReadBytesExt + WriteBytesExt {
+impl<R> Formatter<bool> for R where R: ByteBuffer {
 
     fn serialize(&mut self, offset: u64, value: bool) -> ZeroFormatterResult<i32> {
         let i: u8 = if value { 1 } else { 0 };
@@ -34,44 +37,82 @@ impl<R> Formatter<bool> for R where R: Seek + ReadBytesExt + WriteBytesExt {
     }
 
     fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<bool> {
+        let start_offset = *offset;
         let n: u8 = try!(self.deserialize(offset));
         if n == 1 { Ok(true) }
         else if n == 0 { Ok(false) }
-        else { ZeroFormatterError::invalid_binary(*offset) }
+        else { ZeroFormatterError::invalid(ErrorKind::InvalidBool(n), start_offset) }
+    }
+
+    fn length() -> Option<i32> {
+        Some(1)
     }
 }
 
-impl<R> Formatter<i8> for R where R: Seek + ReadBytesExt + WriteBytesExt {
+impl<R> Formatter<i8> for R where R: ByteBuffer {
 
     fn serialize(&mut self, offset: u64, value: i8) -> ZeroFormatterResult<i32> {
-        try!(self.seek(SeekFrom::Start(offset)));
-        try!(self.write_i8(value));
+        try!(self.write_at(offset, &[value as u8]));
         Ok(1)
     }
 
     fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<i8> {
-        try!(self.seek(SeekFrom::Start(*(offset as &u64))));
-        let n = try!(self.read_i8());
+        let mut buf = [0; 1];
+        try!(self.read_at(*offset, &mut buf));
         *offset += 1;
-        Ok(n)
+        Ok(buf[0] as i8)
+    }
+
+    fn length() -> Option<i32> {
+        Some(1)
+    }
+}
+
+/// `char` is a UTF-16 code unit, so a character outside the Basic Multilingual Plane is an error.
+impl<R> Formatter<char> for R where R: ByteBuffer {
+
+    fn serialize(&mut self, offset: u64, value: char) -> ZeroFormatterResult<i32> {
+        let c = value as u32;
+        if c > 0xffff {
+            return ZeroFormatterError::invalid(ErrorKind::CharOutOfRange(value), offset);
+        }
+        self.serialize(offset, c as u16)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<char> {
+        let start_offset = *offset;
+        let n: u16 = try!(self.deserialize(offset));
+        match char::from_u32(n as u32) {
+            Some(c) => Ok(c),
+            None => ZeroFormatterError::invalid(ErrorKind::InvalidChar(n), start_offset)
+        }
+    }
+
+    fn length() -> Option<i32> {
+        Some(2)
     }
 }
 
 macro_rules! primitive_formatter_impl {
     ($($t:ty; $w:tt; $r:tt; $l:expr),*) => ($(
-        impl<R> Formatter<$t> for R where R: Seek + ReadBytesExt + WriteBytesExt {
+        impl<R> Formatter<$t> for R where R: ByteBuffer {
 
             fn serialize(&mut self, offset: u64, value: $t) -> ZeroFormatterResult<i32> {
-                try!(self.seek(SeekFrom::Start(offset)));
-                try!(self.$w::<LittleEndian>(value));
+                let mut buf = [0; $l];
+                LittleEndian::$w(&mut buf, value);
+                try!(self.write_at(offset, &buf));
                 Ok($l)
             }
 
             fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<$t> {
-                try!(self.seek(SeekFrom::Start(*(offset as &u64))));
-                let n = try!(self.$r::<LittleEndian>());
+                let mut buf = [0; $l];
+                try!(self.read_at(*offset, &mut buf));
                 *offset += $l;
-                Ok(n)
+                Ok(LittleEndian::$r(&buf))
+            }
+
+            fn length() -> Option<i32> {
+                Some($l)
             }
         }
     )*)
@@ -88,16 +129,13 @@ primitive_formatter_impl! {
     f64; write_f64; read_f64; 8
 }
 
-impl<'a, R> Formatter<Cow<'a, str>> for R where R: Seek + ReadBytesExt + WriteBytesExt {
+impl<'a, R> Formatter<Cow<'a, str>> for R where R: ByteBuffer {
 
     fn serialize(&mut self, offset: u64, value: Cow<'a, str>) -> ZeroFormatterResult<i32> {
         let bytes = value.deref().as_bytes();
-        let l = bytes.len();
-        //let i = try!(i32::try_from(l));
-        let i = l as i32;
-        try!(self.seek(SeekFrom::Start(offset)));
-        try!(self.write_i32::<LittleEndian>(i));
-        try!(self.write(bytes));
+        let i = try!(util::length_to_i32(bytes.len(), offset));
+        try!(self.serialize(offset, i));
+        try!(self.write_at(offset + 4, bytes));
         Ok(i + 4)
     }
 
@@ -105,13 +143,40 @@ impl<'a, R> Formatter<Cow<'a, str>> for R where R: Seek + ReadBytesExt + WriteBy
         let i: i32 = try!(util::check_non_null(self, offset));
         //let l = try!(usize::try_from(i));
         let l = i as usize;
-        let mut buf = Vec::with_capacity(l);
-        unsafe { buf.set_len(l); }
-        try!(self.read(&mut buf));
+        let mut buf = vec![0; l];
+        try!(self.read_at(*offset, &mut buf));
         *offset += l as u64;
         let s = try!(String::from_utf8(buf));
         Ok(s.into())
     }
+
+    fn serialized_size(value: &Cow<'a, str>) -> ZeroFormatterResult<i32> {
+        Ok(4 + value.len() as i32)
+    }
+}
+
+impl<R> Formatter<String> for R where R: ByteBuffer {
+
+    fn serialize(&mut self, offset: u64, value: String) -> ZeroFormatterResult<i32> {
+        self.serialize(offset, Cow::from(value))
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<String> {
+        let s: Cow<str> = try!(self.deserialize(offset));
+        Ok(s.into_owned())
+    }
+
+    fn serialized_size(value: &String) -> ZeroFormatterResult<i32> {
+        Ok(4 + value.len() as i32)
+    }
+}
+
+impl<'a, R> Formatter<Option<Cow<'a, str>>> for R where R: ByteBuffer {
+    option_formatter_methods! { Cow<'a, str> }
+}
+
+impl<R> Formatter<Option<String>> for R where R: ByteBuffer {
+    option_formatter_methods! { String }
 }
 
 #[cfg(test)]
@@ -119,6 +184,7 @@ mod tests {
 
     use std::io::Cursor;
     use std::borrow::Cow;
+    use error::*;
     use formatter::*;
 
     #[test]
@@ -163,6 +229,45 @@ mod tests {
         assert_eq!(1u8, rdr.deserialize(&mut offset).unwrap());
     }
 
+    #[test]
+    fn serialize_char() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, 'あ').unwrap(), 2);
+        assert_eq!(wtr.into_inner(), vec![0x42, 0x30]);
+    }
+
+    #[test]
+    fn deserialize_char() {
+        let mut rdr = Cursor::new(vec![0x42, 0x30]);
+        let mut offset = 0;
+        assert_eq!('あ', rdr.deserialize(&mut offset).unwrap());
+        assert_eq!(offset, 2);
+    }
+
+    #[test]
+    fn serialize_char_surrogate_pair() {
+        let mut wtr = Cursor::new(Vec::new());
+        let e = wtr.serialize(2, '🍣').unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::CharOutOfRange('🍣')));
+        assert_eq!(e.offset(), Some(2));
+    }
+
+    #[test]
+    fn deserialize_char_unpaired_surrogate() {
+        let mut rdr = Cursor::new(vec![0x3c, 0xd8]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<char> = rdr.deserialize(&mut offset);
+        assert_eq!(actual.unwrap_err().kind(), Some(ErrorKind::InvalidChar(0xd83c)));
+    }
+
+    #[test]
+    fn deserialize_char_high_surrogate() {
+        let mut rdr = Cursor::new(vec![0, 0, 0x00, 0xd8]);
+        let mut offset = 2;
+        let actual: ZeroFormatterResult<char> = rdr.deserialize(&mut offset);
+        assert_eq!(actual.unwrap_err().to_string(), "[offset 2] 0xd800 is not a UTF-16 code unit of a char");
+    }
+
     #[test]
     fn serialize_u16() {
         let mut wtr = Cursor::new(Vec::new());
@@ -304,4 +409,74 @@ mod tests {
         assert_eq!(offset, 19);
         assert_eq!(Cow::Borrowed("あいうえお"), actual);
     }
+
+    #[test]
+    fn serialize_deserialize_string() {
+        let mut c = Cursor::new(Vec::new());
+        assert_eq!(c.serialize(0, "abc".to_string()).unwrap(), 7);
+        let mut offset = 0;
+        let actual: String = c.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 7);
+        assert_eq!("abc", actual);
+    }
+
+    #[test]
+    fn serialize_str_none() {
+        let mut wtr = Cursor::new(Vec::new());
+        let input: Option<Cow<str>> = None;
+        assert_eq!(wtr.serialize(0, input).unwrap(), 4);
+        assert_eq!(wtr.into_inner(), vec![0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn deserialize_str_none() {
+        let mut rdr = Cursor::new(vec![0xff, 0xff, 0xff, 0xff]);
+        let mut offset = 0;
+        let actual: Option<Cow<'static, str>> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 4);
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn serialize_str_some() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, Some(Cow::Borrowed("a"))).unwrap(), 5);
+        assert_eq!(wtr.into_inner(), vec![1, 0, 0, 0, 0x61]);
+    }
+
+    #[test]
+    fn deserialize_string_some() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0, 0x61]);
+        let mut offset = 0;
+        let actual: Option<String> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 5);
+        assert_eq!(Some("a".to_string()), actual);
+    }
+
+    #[test]
+    fn deserialize_string_invalid_length() {
+        let mut rdr = Cursor::new(vec![0xfe, 0xff, 0xff, 0xff]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<Option<String>> = rdr.deserialize(&mut offset);
+        let e = actual.unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::NegativeLength(-2)));
+        assert_eq!(e.offset(), Some(0));
+    }
+
+    #[test]
+    fn deserialize_bool_invalid() {
+        let mut rdr = Cursor::new(vec![0, 2]);
+        let mut offset = 1;
+        let actual: ZeroFormatterResult<bool> = rdr.deserialize(&mut offset);
+        assert_eq!(actual.unwrap_err().to_string(), "[offset 1] 2 is not a bool byte");
+    }
+
+    #[test]
+    fn serialized_size_string() {
+        let input = "あいう".to_string();
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<String>>::serialized_size(&input).unwrap(), 13);
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<i64>>::serialized_size(&1).unwrap(), 8);
+        let none: Option<Cow<str>> = None;
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Option<Cow<str>>>>::serialized_size(&none).unwrap(), 4);
+    }
 }
@@ -1,28 +1,28 @@
 use error::*;
 use formatter::*;
 use util;
+use buffer::ByteBuffer;
 
-use std::io::Seek;
-use byteorder::{ReadBytesExt, WriteBytesExt};
 use std::borrow::Cow;
 
 fn try_deserialize<R, A>(s: &mut R, n: i32, v: &mut Vec<A>, offset: &mut u64) -> ZeroFormatterResult<()>
-    where R: Seek + ReadBytesExt + WriteBytesExt + Formatter<A> {
+    where R: ByteBuffer + Formatter<A> {
     if n < 0 {
       Ok(())
     } else {
-      let a = try!(s.deserialize(offset));
+      let a = try!(s.deserialize(offset).map_err(|e| e.at_index(v.len())));
       v.push(a);
       try_deserialize(s, n - 1, v, offset)
     }
 }
 
 impl<'a, R, A: Clone> Formatter<Cow<'a, [A]>> for R
-    where R: Seek + ReadBytesExt + WriteBytesExt + Formatter<A> + Formatter<i32> {
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
 
     fn serialize(&mut self, offset: u64, value: Cow<'a, [A]>) -> ZeroFormatterResult<i32> {
         let v: Vec<A> = value.into_owned();
-        let lr = try!(self.serialize(offset, v.len() as i32));
+        let length = try!(util::length_to_i32(v.len(), offset));
+        let lr = try!(self.serialize(offset, length));
         let byte_size = try!(v.iter().fold(
             Ok(lr),
             |b, a| {
@@ -41,6 +41,20 @@ impl<'a, R, A: Clone> Formatter<Cow<'a, [A]>> for R
         try!(try_deserialize(self, l - 1, &mut v, offset));
         Ok(Cow::from(v))
     }
+
+    fn serialized_size(value: &Cow<'a, [A]>) -> ZeroFormatterResult<i32> {
+        let mut byte_size: i32 = 4;
+        for a in value.iter() {
+            byte_size += try!(<R as Formatter<A>>::serialized_size(a));
+        }
+        Ok(byte_size)
+    }
+}
+
+impl<'a, R, A: Clone> Formatter<Option<Cow<'a, [A]>>> for R
+    where R: ByteBuffer + Formatter<A> + Formatter<i32> {
+
+    option_formatter_methods! { Cow<'a, [A]> }
 }
 
 #[cfg(test)]
@@ -48,7 +62,6 @@ mod tests {
 
     use std::io::Cursor;
     use std::borrow::Cow;
-    use std::io::{Seek, SeekFrom};
     use error::*;
     use formatter::*;
     use util;
@@ -69,6 +82,48 @@ mod tests {
         assert_eq!(Cow::from(vec![1, 2, 3]), actual);
     }
 
+    #[test]
+    fn serialize_vec_char() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, Cow::from(vec!['a', 'b'])).unwrap(), 8);
+        assert_eq!(wtr.into_inner(), vec![2, 0, 0, 0, 0x61, 0, 0x62, 0]);
+    }
+
+    #[test]
+    fn deserialize_vec_char() {
+        let mut rdr = Cursor::new(vec![2, 0, 0, 0, 0x61, 0, 0x62, 0]);
+        let mut offset = 0;
+        let actual: Cow<'static, [char]> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 8);
+        assert_eq!(Cow::from(vec!['a', 'b']), actual);
+    }
+
+    #[test]
+    fn serialize_vec_none() {
+        let mut wtr = Cursor::new(Vec::new());
+        let input: Option<Cow<[i32]>> = None;
+        assert_eq!(wtr.serialize(0, input).unwrap(), 4);
+        assert_eq!(wtr.into_inner(), vec![0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn deserialize_vec_none() {
+        let mut rdr = Cursor::new(vec![0xff, 0xff, 0xff, 0xff]);
+        let mut offset = 0;
+        let actual: Option<Cow<'static, [i32]>> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 4);
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn deserialize_vec_some() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0, 1, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: Option<Cow<'static, [i32]>> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 8);
+        assert_eq!(Some(Cow::from(vec![1])), actual);
+    }
+
     struct_formatter! {
         #[target(Cursor<Vec<u8>>)]
         S {
@@ -149,4 +204,12 @@ mod tests {
         assert_eq!(offset, 52);
         assert_eq!(Cow::from(vec![O{a: 1}, O{a: 2}, O{a: 3}]), actual);
     }
+
+    #[test]
+    fn serialized_size_sequence() {
+        let input: Cow<[Cow<'static, str>]> = Cow::Owned(vec![Cow::Borrowed("a"), Cow::Borrowed("bc")]);
+        let mut wtr = Cursor::new(Vec::new());
+        let expected = wtr.serialize(0, input.clone()).unwrap();
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Cow<[Cow<str>]>>>::serialized_size(&input).unwrap(), expected);
+    }
 }
@@ -0,0 +1,534 @@
+//! Serialize a Rust data structure into ZeroFormatter binary by serde.
+//!
+//! | serde | ZeroFormatter | Note |
+//! | ---- | ---- | --- |
+//! | primitive | Primitive | 128 bit integers are not supported |
+//! | char | `Char` | UTF-16 code unit |
+//! | string | `String` | |
+//! | bytes, seq | `Sequence<T>` | |
+//! | tuple, tuple struct | Tuple / Struct | |
+//! | struct | Struct | if struct is registered to `ObjectIndexes`, Object |
+//! | map | Dictionary | |
+//! | option | `T?` | has value format. use `nullable` module for -1 length format |
+//! | enum | Union | union key is variant index (`i32`) |
+
+use error::*;
+use formatter::*;
+
+use std::fmt::Display;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use buffer::ByteBuffer;
+use serde::ser::{self, Serialize};
+
+#[doc(hidden)]
+pub const NULLABLE: &str = "$zero_formatter::Nullable";
+
+/// `ObjectIndexes` provide field indexes of structs which are serialized as Object format.
+/// Structs that are not registered are serialized as Struct format.
+///
+/// ```
+/// # use zero_formatter::ser::ObjectIndexes;
+/// let indexes = ObjectIndexes::new()
+///     .object("ObjectSample", &[(0, "a"), (1, "b")]);
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct ObjectIndexes {
+    objects: HashMap<&'static str, Vec<(i32, &'static str)>>
+}
+
+impl ObjectIndexes {
+
+    pub fn new() -> ObjectIndexes {
+        ObjectIndexes { objects: HashMap::new() }
+    }
+
+    pub fn object(mut self, name: &'static str, fields: &[(i32, &'static str)]) -> ObjectIndexes {
+        self.objects.insert(name, fields.to_vec());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[(i32, &'static str)]> {
+        self.objects.get(name).map(|v| v.as_slice())
+    }
+}
+
+pub(crate) fn last_index(fields: &[(i32, &'static str)]) -> i32 {
+    fields.iter().map(|&(i, _)| i).max().unwrap_or(-1)
+}
+
+pub(crate) fn field_index(fields: &[(i32, &'static str)], name: &str) -> Option<i32> {
+    fields.iter().find(|&&(_, n)| n == name).map(|&(i, _)| i)
+}
+
+/// `Serializer` write values from `offset`, and return written byte size.
+pub struct Serializer<'a, W> {
+    writer: W,
+    offset: u64,
+    indexes: &'a ObjectIndexes,
+    nullable: bool
+}
+
+impl<'a, W> Serializer<'a, W> where W: ByteBuffer {
+
+    pub fn new(writer: W, offset: u64, indexes: &'a ObjectIndexes) -> Serializer<'a, W> {
+        Serializer { writer, offset, indexes, nullable: false }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write<T>(&mut self, value: T) -> ZeroFormatterResult<i32> where W: Formatter<T> {
+        let n = try!(self.writer.serialize(self.offset, value));
+        self.offset += n as u64;
+        Ok(n)
+    }
+
+    fn write_at<T>(&mut self, offset: u64, value: T) -> ZeroFormatterResult<i32> where W: Formatter<T> {
+        self.writer.serialize(offset, value)
+    }
+}
+
+pub fn to_writer<W, T>(writer: W, offset: u64, value: &T) -> ZeroFormatterResult<i32>
+    where W: ByteBuffer, T: ?Sized + Serialize {
+    to_writer_with_indexes(writer, offset, value, &ObjectIndexes::new())
+}
+
+pub fn to_writer_with_indexes<W, T>(writer: W, offset: u64, value: &T, indexes: &ObjectIndexes) -> ZeroFormatterResult<i32>
+    where W: ByteBuffer, T: ?Sized + Serialize {
+    let mut serializer = Serializer::new(writer, offset, indexes);
+    value.serialize(&mut serializer)
+}
+
+impl ser::Error for ZeroFormatterError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ZeroFormatterError::Message(msg.to_string())
+    }
+}
+
+fn unsupported<T>(name: &str) -> ZeroFormatterResult<T> {
+    Err(ZeroFormatterError::Message(format!("{} is not supported", name)))
+}
+
+impl<'a, 'b, W> ser::Serializer for &'a mut Serializer<'b, W> where W: ByteBuffer {
+    type Ok = i32;
+    type Error = ZeroFormatterError;
+
+    type SerializeSeq = Compound<'a, 'b, W>;
+    type SerializeTuple = Compound<'a, 'b, W>;
+    type SerializeTupleStruct = Compound<'a, 'b, W>;
+    type SerializeTupleVariant = Compound<'a, 'b, W>;
+    type SerializeMap = Compound<'a, 'b, W>;
+    type SerializeStruct = Compound<'a, 'b, W>;
+    type SerializeStructVariant = Compound<'a, 'b, W>;
+
+    fn serialize_bool(self, v: bool) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_i8(self, v: i8) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_i16(self, v: i16) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_i32(self, v: i32) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_i64(self, v: i64) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_u8(self, v: u8) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_u16(self, v: u16) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_u32(self, v: u32) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_u64(self, v: u64) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_f32(self, v: f32) -> ZeroFormatterResult<i32> { self.write(v) }
+    fn serialize_f64(self, v: f64) -> ZeroFormatterResult<i32> { self.write(v) }
+
+    fn serialize_char(self, v: char) -> ZeroFormatterResult<i32> { self.write(v) }
+
+    fn serialize_str(self, v: &str) -> ZeroFormatterResult<i32> {
+        self.write(Cow::Borrowed(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> ZeroFormatterResult<i32> {
+        self.write(Cow::Borrowed(v))
+    }
+
+    fn serialize_none(self) -> ZeroFormatterResult<i32> {
+        if self.nullable {
+            self.nullable = false;
+            self.write(-1i32)
+        } else {
+            self.write(false)
+        }
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> ZeroFormatterResult<i32> {
+        if self.nullable {
+            self.nullable = false;
+            value.serialize(self)
+        } else {
+            let r1 = try!(self.write(true));
+            let r2 = try!(value.serialize(&mut *self));
+            Ok(r1 + r2)
+        }
+    }
+
+    fn serialize_unit(self) -> ZeroFormatterResult<i32> {
+        Ok(0)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> ZeroFormatterResult<i32> {
+        Ok(0)
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, variant_index: u32, _: &'static str) -> ZeroFormatterResult<i32> {
+        let start = self.offset;
+        self.offset += 4;
+        let key = try!(self.write(variant_index as i32));
+        let byte_size = 4 + key;
+        try!(self.write_at(start, byte_size));
+        Ok(byte_size)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, name: &'static str, value: &T) -> ZeroFormatterResult<i32> {
+        if name == NULLABLE {
+            self.nullable = true;
+        }
+        let r = value.serialize(&mut *self);
+        self.nullable = false;
+        r
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _: &'static str, variant_index: u32, _: &'static str, value: &T) -> ZeroFormatterResult<i32> {
+        let start = self.offset;
+        self.offset += 4;
+        let key = try!(self.write(variant_index as i32));
+        let v = try!(value.serialize(&mut *self));
+        let byte_size = 4 + key + v;
+        try!(self.write_at(start, byte_size));
+        Ok(byte_size)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> ZeroFormatterResult<Compound<'a, 'b, W>> {
+        let start = self.offset;
+        self.offset += 4;
+        Ok(Compound::new(self, start, 4, Layout::Sequence))
+    }
+
+    fn serialize_tuple(self, _: usize) -> ZeroFormatterResult<Compound<'a, 'b, W>> {
+        let start = self.offset;
+        Ok(Compound::new(self, start, 0, Layout::Struct))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> ZeroFormatterResult<Compound<'a, 'b, W>> {
+        let start = self.offset;
+        Ok(Compound::new(self, start, 0, Layout::Struct))
+    }
+
+    fn serialize_tuple_variant(self, _: &'static str, variant_index: u32, _: &'static str, _: usize) -> ZeroFormatterResult<Compound<'a, 'b, W>> {
+        let start = self.offset;
+        self.offset += 4;
+        let key = try!(self.write(variant_index as i32));
+        Ok(Compound::new(self, start, 4 + key, Layout::Union))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> ZeroFormatterResult<Compound<'a, 'b, W>> {
+        let start = self.offset;
+        self.offset += 4;
+        Ok(Compound::new(self, start, 4, Layout::Sequence))
+    }
+
+    fn serialize_struct(self, name: &'static str, _: usize) -> ZeroFormatterResult<Compound<'a, 'b, W>> {
+        let start = self.offset;
+        match self.indexes.get(name) {
+            None => Ok(Compound::new(self, start, 0, Layout::Struct)),
+            Some(fields) => {
+                let last_index = last_index(fields);
+                try!(self.write_at(start + 4, last_index));
+                for i in 0..(last_index + 1) {
+                    try!(self.write_at(start + 4 + 4 + 4 * (i as u64), 0i32));
+                }
+                let byte_size = 4 + 4 + 4 * (last_index + 1);
+                self.offset = start + (byte_size as u64);
+                Ok(Compound::new(self, start, byte_size, Layout::Object(fields)))
+            }
+        }
+    }
+
+    fn serialize_struct_variant(self, name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> ZeroFormatterResult<Compound<'a, 'b, W>> {
+        self.serialize_tuple_variant(name, variant_index, variant, len)
+    }
+
+    fn serialize_i128(self, _: i128) -> ZeroFormatterResult<i32> {
+        unsupported("i128")
+    }
+
+    fn serialize_u128(self, _: u128) -> ZeroFormatterResult<i32> {
+        unsupported("u128")
+    }
+}
+
+#[doc(hidden)]
+pub enum Layout<'a> {
+    /// `[length:int][elements...]`
+    Sequence,
+    /// `[elements...]`
+    Struct,
+    /// `[byteSize:int][lastIndex:int][indexOffset...:int][elements...]`
+    Object(&'a [(i32, &'static str)]),
+    /// `[byteSize:int][unionKey:int][elements...]`
+    Union
+}
+
+#[doc(hidden)]
+pub struct Compound<'a, 'b: 'a, W: 'a> {
+    ser: &'a mut Serializer<'b, W>,
+    start: u64,
+    byte_size: i32,
+    length: i32,
+    layout: Layout<'b>
+}
+
+impl<'a, 'b, W> Compound<'a, 'b, W> where W: ByteBuffer {
+
+    fn new(ser: &'a mut Serializer<'b, W>, start: u64, byte_size: i32, layout: Layout<'b>) -> Compound<'a, 'b, W> {
+        Compound { ser, start, byte_size, length: 0, layout }
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> ZeroFormatterResult<()> {
+        self.byte_size += try!(value.serialize(&mut *self.ser));
+        self.length += 1;
+        Ok(())
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> ZeroFormatterResult<()> {
+        let index = match self.layout {
+            Layout::Object(fields) => match field_index(fields, key) {
+                Some(i) => Some(i),
+                None => return Err(ZeroFormatterError::Message(format!("field `{}` does not have index", key)))
+            },
+            _ => None
+        };
+        if let Some(i) = index {
+            let o = self.ser.offset as i32;
+            try!(self.ser.write_at(self.start + 4 + 4 + 4 * (i as u64), o));
+        }
+        self.element(value)
+    }
+
+    fn end(self) -> ZeroFormatterResult<i32> {
+        match self.layout {
+            Layout::Sequence => { try!(self.ser.write_at(self.start, self.length)); },
+            Layout::Object(_) | Layout::Union => { try!(self.ser.write_at(self.start, self.byte_size)); },
+            Layout::Struct => {}
+        }
+        Ok(self.byte_size)
+    }
+}
+
+impl<'a, 'b, W> ser::SerializeSeq for Compound<'a, 'b, W> where W: ByteBuffer {
+    type Ok = i32;
+    type Error = ZeroFormatterError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> ZeroFormatterResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> ZeroFormatterResult<i32> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, W> ser::SerializeTuple for Compound<'a, 'b, W> where W: ByteBuffer {
+    type Ok = i32;
+    type Error = ZeroFormatterError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> ZeroFormatterResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> ZeroFormatterResult<i32> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, W> ser::SerializeTupleStruct for Compound<'a, 'b, W> where W: ByteBuffer {
+    type Ok = i32;
+    type Error = ZeroFormatterError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> ZeroFormatterResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> ZeroFormatterResult<i32> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, W> ser::SerializeTupleVariant for Compound<'a, 'b, W> where W: ByteBuffer {
+    type Ok = i32;
+    type Error = ZeroFormatterError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> ZeroFormatterResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> ZeroFormatterResult<i32> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, W> ser::SerializeMap for Compound<'a, 'b, W> where W: ByteBuffer {
+    type Ok = i32;
+    type Error = ZeroFormatterError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> ZeroFormatterResult<()> {
+        self.byte_size += try!(key.serialize(&mut *self.ser));
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> ZeroFormatterResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> ZeroFormatterResult<i32> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, W> ser::SerializeStruct for Compound<'a, 'b, W> where W: ByteBuffer {
+    type Ok = i32;
+    type Error = ZeroFormatterError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> ZeroFormatterResult<()> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> ZeroFormatterResult<i32> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, W> ser::SerializeStructVariant for Compound<'a, 'b, W> where W: ByteBuffer {
+    type Ok = i32;
+    type Error = ZeroFormatterError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> ZeroFormatterResult<()> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> ZeroFormatterResult<i32> {
+        Compound::end(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+    use ser::*;
+
+    #[derive(Serialize)]
+    struct S {
+        a: i32,
+        b: i64
+    }
+
+    #[derive(Serialize)]
+    struct O {
+        a: i32,
+        b: i64
+    }
+
+    #[derive(Serialize)]
+    enum U {
+        A(O),
+        B(S),
+        C
+    }
+
+    #[derive(Serialize)]
+    struct N {
+        #[serde(with = "::nullable")]
+        a: Option<String>,
+        b: Option<i32>
+    }
+
+    fn indexes() -> ObjectIndexes {
+        ObjectIndexes::new()
+            .object("O", &[(0, "a"), (1, "b")])
+    }
+
+    #[test]
+    fn serialize_struct() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer(&mut wtr, 0, &S { a: 1, b: 2 }).unwrap(), 12);
+        assert_eq!(wtr.into_inner(), vec![1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serialize_object() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer_with_indexes(&mut wtr, 0, &O { a: 1, b: 2 }, &indexes()).unwrap(), 28);
+        assert_eq!(wtr.into_inner(), vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serialize_object_without_index() {
+        let mut wtr = Cursor::new(Vec::new());
+        let indexes = ObjectIndexes::new().object("O", &[(0, "a")]);
+        assert!(to_writer_with_indexes(&mut wtr, 0, &O { a: 1, b: 2 }, &indexes).is_err());
+    }
+
+    #[test]
+    fn serialize_vec() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer(&mut wtr, 0, &vec![1i32, 2i32, 3i32]).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serialize_char() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer(&mut wtr, 0, &'あ').unwrap(), 2);
+        assert_eq!(wtr.into_inner(), vec![0x42, 0x30]);
+    }
+
+    #[test]
+    fn serialize_str() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer(&mut wtr, 0, "あいうえお").unwrap(), 19);
+        assert_eq!(wtr.into_inner(), vec![0x0f, 0, 0, 0, 0xe3, 0x81, 0x82, 0xe3, 0x81, 0x84, 0xe3, 0x81, 0x86, 0xe3, 0x81, 0x88, 0xe3, 0x81, 0x8a]);
+    }
+
+    #[test]
+    fn serialize_union() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer_with_indexes(&mut wtr, 0, &U::A(O { a: 1, b: 2 }), &indexes()).unwrap(), 36);
+        assert_eq!(wtr.into_inner(), vec![
+            36, 0, 0, 0,
+            0, 0, 0, 0,
+            28, 0, 0, 0, 1, 0, 0, 0, 24, 0, 0, 0, 28, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0
+        ]);
+    }
+
+    #[test]
+    fn serialize_union_struct() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer(&mut wtr, 0, &U::B(S { a: 1, b: 2 })).unwrap(), 20);
+        assert_eq!(wtr.into_inner(), vec![20, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serialize_union_unit() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer(&mut wtr, 0, &U::C).unwrap(), 8);
+        assert_eq!(wtr.into_inner(), vec![8, 0, 0, 0, 2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serialize_option() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer(&mut wtr, 0, &N { a: None, b: Some(1) }).unwrap(), 9);
+        assert_eq!(wtr.into_inner(), vec![0xff, 0xff, 0xff, 0xff, 1, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serialize_nullable_some() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(to_writer(&mut wtr, 0, &N { a: Some("a".to_string()), b: None }).unwrap(), 6);
+        assert_eq!(wtr.into_inner(), vec![1, 0, 0, 0, 0x61, 0]);
+    }
+}
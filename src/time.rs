@@ -1,12 +1,11 @@
 use error::*;
 use formatter::*;
 
-use std::io::Seek;
-use byteorder::{ReadBytesExt, WriteBytesExt};
-use chrono::{UTC, DateTime, TimeZone};
+use buffer::ByteBuffer;
+use chrono::{UTC, FixedOffset, DateTime, TimeZone};
 use std::time::Duration;
 
-impl<R> Formatter<DateTime<UTC>> for R where R: Seek + ReadBytesExt + WriteBytesExt {
+impl<R> Formatter<DateTime<UTC>> for R where R: ByteBuffer {
 
     fn serialize(&mut self, offset: u64, value: DateTime<UTC>) -> ZeroFormatterResult<i32> {
         let seconds = try!(self.serialize(offset, value.timestamp()));
@@ -19,9 +18,44 @@ impl<R> Formatter<DateTime<UTC>> for R where R: Seek + ReadBytesExt + WriteBytes
         let nanos: i32 = try!(self.deserialize(offset));
         Ok(UTC.timestamp(seconds, nanos as u32))
     }
+
+    fn length() -> Option<i32> {
+        Some(12)
+    }
+}
+
+/// DateTimeOffset keeps the offset in minutes, so sub-minute offsets are truncated.
+impl<R> Formatter<DateTime<FixedOffset>> for R where R: ByteBuffer {
+
+    fn serialize(&mut self, offset: u64, value: DateTime<FixedOffset>) -> ZeroFormatterResult<i32> {
+        let seconds = try!(self.serialize(offset, value.timestamp()));
+        let nanos = try!(self.serialize(offset + 8, value.timestamp_subsec_nanos() as i32));
+        let minutes = try!(self.serialize(offset + 12, (value.offset().local_minus_utc() / 60) as i16));
+        Ok(seconds + nanos + minutes)
+    }
+
+    fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<DateTime<FixedOffset>> {
+        let start_offset = *offset;
+        let seconds: i64 = try!(self.deserialize(offset));
+        let nanos: i32 = try!(self.deserialize(offset));
+        let minutes_offset = *offset;
+        let minutes: i16 = try!(self.deserialize(offset));
+        let tz = match FixedOffset::east_opt(minutes as i32 * 60) {
+            Some(tz) => tz,
+            None => return ZeroFormatterError::invalid(ErrorKind::InvalidUtcOffset(minutes), minutes_offset)
+        };
+        match tz.timestamp_opt(seconds, nanos as u32).single() {
+            Some(dt) => Ok(dt),
+            None => ZeroFormatterError::invalid(ErrorKind::InvalidDateTime, start_offset)
+        }
+    }
+
+    fn length() -> Option<i32> {
+        Some(14)
+    }
 }
 
-impl<R> Formatter<Duration> for R where R: Seek + ReadBytesExt + WriteBytesExt {
+impl<R> Formatter<Duration> for R where R: ByteBuffer {
 
     fn serialize(&mut self, offset: u64, value: Duration) -> ZeroFormatterResult<i32> {
         let seconds = try!(self.serialize(offset, value.as_secs() as i64));
@@ -34,13 +68,19 @@ impl<R> Formatter<Duration> for R where R: Seek + ReadBytesExt + WriteBytesExt {
         let nanos: i32 = try!(self.deserialize(offset));
         Ok(Duration::new(seconds as u64, nanos as u32))
     }
+
+    fn length() -> Option<i32> {
+        Some(12)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use std::io::Cursor;
-    use chrono::UTC;
+    use chrono::{UTC, FixedOffset, TimeZone};
+    use chrono::DateTime;
+    use error::*;
     use formatter::*;
     use std::time::Duration;
 
@@ -50,7 +90,52 @@ mod tests {
         let mut wtr = Cursor::new(Vec::new());
         assert_eq!(wtr.serialize(0, dt).unwrap(), 12);
         let mut offset = 0;
-        assert_eq!(dt, wtr.deserialize(&mut offset).unwrap());
+        let actual: DateTime<UTC> = wtr.deserialize(&mut offset).unwrap();
+        assert_eq!(dt, actual);
+    }
+
+    #[test]
+    fn serialize_datetime_offset() {
+        let dt = FixedOffset::east(9 * 3600).timestamp(1, 2);
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, dt).unwrap(), 14);
+        assert_eq!(wtr.into_inner(), vec![1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0x1c, 0x02]);
+    }
+
+    #[test]
+    fn deserialize_datetime_offset() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0xe4, 0xfd]);
+        let mut offset = 0;
+        let actual: DateTime<FixedOffset> = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(offset, 14);
+        assert_eq!(actual, FixedOffset::west(9 * 3600).timestamp(1, 2));
+        assert_eq!(actual.offset().local_minus_utc(), -9 * 3600);
+    }
+
+    #[test]
+    fn deserialize_datetime_offset_out_of_range() {
+        let mut rdr = Cursor::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0xff, 0x7f]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<DateTime<FixedOffset>> = rdr.deserialize(&mut offset);
+        let e = actual.unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::InvalidUtcOffset(0x7fff)));
+        assert_eq!(e.offset(), Some(12));
+    }
+
+    #[test]
+    fn deserialize_datetime_offset_invalid_nanos() {
+        let mut rdr = Cursor::new(vec![0, 0, 1, 0, 0, 0, 0, 0, 0, 0x94, 0x35, 0x77, 0, 0]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<DateTime<FixedOffset>> = rdr.deserialize(&mut offset);
+        assert_eq!(actual.unwrap_err().kind(), Some(ErrorKind::InvalidDateTime));
+    }
+
+    #[test]
+    fn deserialize_datetime_offset_invalid_seconds() {
+        let mut rdr = Cursor::new(vec![0, 0, 0, 0, 0, 0, 0, 0x80, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<DateTime<FixedOffset>> = rdr.deserialize(&mut offset);
+        assert_eq!(actual.unwrap_err().kind(), Some(ErrorKind::InvalidDateTime));
     }
 
     #[test]
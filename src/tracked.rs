@@ -0,0 +1,185 @@
+use std::ops::{Deref, DerefMut};
+use byteorder::{ByteOrder, LittleEndian};
+
+/// `Tracked` keeps the bytes of the deserialized Object format, like dirty tracking of ZeroFormatter.
+/// When it is serialized again, an unchanged field is copied from the original bytes instead of being serialized.
+/// The bytes of a fixed length field are copied only if they have the size of the format,
+/// and the bytes of a variable length field are copied only if the field does not move,
+/// because they may contain absolute offsets.
+///
+/// `Tracked<T>` is available for structs which are defined by `object_formatter` or `tracked_formatter`.
+///
+/// ```
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::{Cursor, Result};
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// object_formatter! {
+///     #[target(Buffer<Cursor<Vec<u8>>>)]
+///     ObjectSample {
+///         0; a: i32,
+///         1; b: i64
+///     }
+/// }
+///
+/// # fn example() -> Result<()> {
+/// # let mut buffer = Buffer::new(Cursor::new(Vec::new()));
+/// try!(buffer.serialize(0, ObjectSample { a: 1, b: 2 }));
+/// let mut offset = 0;
+/// let mut tracked: Tracked<ObjectSample> = try!(buffer.deserialize(&mut offset));
+/// tracked.b = 3;
+/// try!(buffer.serialize(0, tracked));
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// # example().unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tracked<T> {
+    value: T,
+    origin: Option<Origin<T>>
+}
+
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct Origin<T> {
+    pub value: T,
+    offset: u64,
+    bytes: Vec<u8>
+}
+
+impl<T> Origin<T> {
+
+    /// Return the absolute position and the bytes of the field at `index` in the original Object format.
+    /// Return `None` if the field is not written, or if the header or the offset does not fit in the original bytes,
+    /// so the field is serialized as usual.
+    pub fn field(&self, index: i32) -> Option<(u64, &[u8])> {
+        if self.bytes.len() < 8 || index < 0 {
+            return None;
+        }
+        let last_index = LittleEndian::read_i32(&self.bytes[4..8]);
+        if index > last_index {
+            return None;
+        }
+        let header_size = 8 + 4 * (last_index as u64 + 1);
+        if header_size > self.bytes.len() as u64 {
+            return None;
+        }
+        let slot = |i: i32| LittleEndian::read_i32(&self.bytes[(8 + 4 * i) as usize..]) as u64;
+        let start = slot(index);
+        let mut end = self.offset + (self.bytes.len() as u64);
+        if start < self.offset + header_size || start >= end {
+            return None;
+        }
+        for i in 0..(last_index + 1) {
+            let o = slot(i);
+            if o > start && o < end {
+                end = o;
+            }
+        }
+        Some((start, &self.bytes[(start - self.offset) as usize..(end - self.offset) as usize]))
+    }
+}
+
+impl<T> Tracked<T> {
+
+    /// Wrap a value which has no original bytes. It is serialized as usual.
+    pub fn new(value: T) -> Tracked<T> {
+        Tracked { value, origin: None }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Return `true` if it has the original bytes.
+    pub fn is_tracked(&self) -> bool {
+        self.origin.is_some()
+    }
+
+    #[doc(hidden)]
+    pub fn from_origin(value: T, origin: T, offset: u64, bytes: Vec<u8>) -> Tracked<T> {
+        Tracked { value, origin: Some(Origin { value: origin, offset, bytes }) }
+    }
+
+    #[doc(hidden)]
+    pub fn into_parts(self) -> (T, Option<Origin<T>>) {
+        (self.value, self.origin)
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// `tracked_formatter` provide formatter of `Tracked<T>` for an existing struct type.
+/// It is used by `object_formatter`. Fields must implement `PartialEq`, and the struct must implement `Clone`.
+#[macro_export]
+macro_rules! tracked_formatter {
+    (#[target($buffer:ty)]
+    $name:ident {
+        $($index:expr; $field_name:ident: $field_type:ty),*
+    }) => {
+        impl Formatter<$crate::Tracked<$name>> for $buffer {
+
+            fn serialize(&mut self, offset: u64, value: $crate::Tracked<$name>) -> ZeroFormatterResult<i32> {
+                let (value, origin) = value.into_parts();
+                let origin = match origin {
+                    Some(o) => o,
+                    None => return self.serialize(offset, value)
+                };
+
+                let last_index: i32 = *([$($index),*].iter().max().unwrap());
+                let mut byte_size: i32 = 4 + 4 + 4 * (last_index + 1);
+
+                try!(self.serialize(offset + 4, last_index));
+
+                $(
+                let position = offset + (byte_size as u64);
+                try!(self.serialize(offset + 4 + 4 + 4 * $index, position as i32));
+                let copied = if value.$field_name == origin.value.$field_name {
+                    match origin.field($index) {
+                        Some((p, bytes)) if <Self as Formatter<$field_type>>::length().map_or(p == position, |l| l as usize == bytes.len()) =>
+                            Some(try!($crate::util::write_bytes(self, position, bytes))),
+                        _ => None
+                    }
+                } else {
+                    None
+                };
+                byte_size += match copied {
+                    Some(n) => n,
+                    None => try!(self.serialize(position, value.$field_name))
+                };
+                )*
+
+                try!(self.serialize(offset, byte_size));
+                Ok(byte_size)
+            }
+
+            fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<$crate::Tracked<$name>> {
+                let start_offset = *offset;
+                let value: $name = try!(self.deserialize(offset));
+                let bytes = try!($crate::util::read_bytes(self, start_offset, *offset - start_offset));
+                Ok($crate::Tracked::from_origin(value.clone(), value, start_offset, bytes))
+            }
+
+            fn serialized_size(value: &$crate::Tracked<$name>) -> ZeroFormatterResult<i32> {
+                <Self as Formatter<$name>>::serialized_size(value)
+            }
+        }
+    }
+}
@@ -1,11 +1,68 @@
+use std::borrow::Cow;
+
+/// `UnionKey` is a type of the key of `union_formatter`.
+/// A key is written as a literal or a path, and it is converted by `from_literal`.
+/// Deserialized keys are matched against the keys through `as_pattern`, so duplicate keys are a compile error.
+/// `enum_formatter` implements it for the enum.
+pub trait UnionKey: Sized {
+    type Literal;
+    type Pattern: ?Sized;
+
+    fn from_literal(literal: Self::Literal) -> Self;
+    fn as_pattern(&self) -> &Self::Pattern;
+}
+
+macro_rules! primitive_union_key_impl {
+    ($($t:ty),*) => ($(
+        impl UnionKey for $t {
+            type Literal = $t;
+            type Pattern = $t;
+
+            fn from_literal(literal: $t) -> $t {
+                literal
+            }
+
+            fn as_pattern(&self) -> &$t {
+                self
+            }
+        }
+    )*)
+}
+
+primitive_union_key_impl! { u8, u16, u32, u64, i8, i16, i32, i64, bool, char }
+
+impl<'a> UnionKey for Cow<'a, str> {
+    type Literal = &'a str;
+    type Pattern = str;
+
+    fn from_literal(literal: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(literal)
+    }
+
+    fn as_pattern(&self) -> &str {
+        self
+    }
+}
+
+impl UnionKey for String {
+    type Literal = &'static str;
+    type Pattern = str;
+
+    fn from_literal(literal: &'static str) -> String {
+        literal.to_string()
+    }
+
+    fn as_pattern(&self) -> &str {
+        self
+    }
+}
+
 /// `union_formatter` define struct type and provide formatter.
 ///
 /// ```
 /// # #[macro_use] extern crate zero_formatter;
-/// # extern crate byteorder;
 /// # use zero_formatter::*;
-/// # use std::io::{Seek, SeekFrom, Read, Write, Cursor, Result};
-/// # use byteorder::{ReadBytesExt, WriteBytesExt};
+/// # use std::io::{Cursor, Result};
 /// #
 /// # declare_buffer! { Buffer }
 /// #
@@ -46,16 +103,183 @@
 /// # example();
 /// # }
 /// ```
-
+///
+/// A case can also be a unit variant, or a struct-like variant.
+/// The fields of a struct-like variant are written as Struct format,
+/// or as Object format if they have indexes like `object_formatter`.
+///
+/// ```
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::{Cursor, Result};
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// union_formatter! {
+///     #[target(Buffer<Cursor<Vec<u8>>>)]
+///     enum Command: i32 {
+///         0; Ping,
+///         1; Move { x: i32, y: i32 },
+///         2; Say { 0; volume: i32, 1; pitch: i64 }
+///     }
+/// }
+///
+/// # fn example() -> Result<()> {
+/// # let mut writer = Buffer::new(Cursor::new(Vec::new()));
+/// try!(writer.serialize(0, Command::Ping));
+/// try!(writer.serialize(0, Command::Move { x: 1, y: 2 }));
+/// try!(writer.serialize(0, Command::Say { volume: 3, pitch: 4 }));
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// # example();
+/// # }
+/// ```
+///
+/// A key can be an integer, a string (`Cow<str>` or `String`), or an enum of `enum_formatter`.
+/// The same key must not be used twice.
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::borrow::Cow;
+/// # use std::io::{Cursor, Result};
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// union_formatter! {
+///     #[target(Buffer<Cursor<Vec<u8>>>)]
+///     enum Duplicate: Cow<'static, str> {
+///         "a"; A(i32),
+///         "a"; B(i64)
+///     }
+/// }
+/// #
+/// # fn main() {}
+/// ```
+///
+/// `#[fallback(Name)]` adds `Name(key, bytes)` case for unknown keys, like `[UnionFallback]` of ZeroFormatter.
+/// It keeps the raw bytes of the value, and serializes them again as they are.
+/// The union skips to the end of the value by byteSize, so the readers can ignore cases added by newer writers.
+///
+/// ```
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::{Cursor, Result};
+/// #
+/// # declare_buffer! { Buffer }
+/// #
+/// # object_formatter! {
+/// #     #[target(Buffer<Cursor<Vec<u8>>>)]
+/// #     AObject {
+/// #         0; a: i32,
+/// #         1; b: i64
+/// #     }
+/// # }
+/// #
+/// union_formatter! {
+///     #[target(Buffer<Cursor<Vec<u8>>>)]
+///     #[fallback(Unknown)]
+///     enum UnionSample: i32 {
+///         0; A(AObject)
+///     }
+/// }
+///
+/// # fn example() -> Result<()> {
+/// # let mut buffer = Buffer::new(Cursor::new(Vec::new()));
+/// try!(buffer.serialize(0, UnionSample::Unknown(1, vec![1, 2, 3])));
+/// let mut offset = 0;
+/// let actual: UnionSample = try!(buffer.deserialize(&mut offset));
+/// assert_eq!(UnionSample::Unknown(1, vec![1, 2, 3]), actual);
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// # example().unwrap();
+/// # }
+/// ```
 #[macro_export]
 macro_rules! union_formatter {
     (#[target($buffer:ty)]
+    #[fallback($fallback:ident)]
     enum $name:ident : $key_type:ty {
-        $($key_value:expr; $case_name:ident($field_type:ty)),*
+        $($body:tt)*
     }) => {
-        #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+        union_formatter! {
+            @parse [$buffer; $name; $key_type; [$fallback]; [Debug, PartialEq, Eq, Clone]]
+            [] [] [] []
+            $($body)*
+        }
+    };
+    (#[target($buffer:ty)]
+    enum $name:ident : $key_type:ty {
+        $($body:tt)*
+    }) => {
+        union_formatter! {
+            @parse [$buffer; $name; $key_type; []; [Debug, PartialEq, Eq, Copy, Clone]]
+            [] [] [] []
+            $($body)*
+        }
+    };
+    (@parse $header:tt $units:tt $tuples:tt $structs:tt $objects:tt) => {
+        union_formatter! { @impl $header $units $tuples $structs $objects }
+    };
+    (@parse $header:tt $units:tt $tuples:tt $structs:tt $objects:tt
+        $key_value:literal; $($rest:tt)*) => {
+        union_formatter! { @case $header $units $tuples $structs $objects [$key_value] $($rest)* }
+    };
+    (@parse $header:tt $units:tt $tuples:tt $structs:tt $objects:tt
+        $key_value:path; $($rest:tt)*) => {
+        union_formatter! { @case $header $units $tuples $structs $objects [$key_value] $($rest)* }
+    };
+    (@case $header:tt [$($units:tt)*] $tuples:tt $structs:tt $objects:tt
+        $key_value:tt $case_name:ident $(, $($rest:tt)*)?) => {
+        union_formatter! {
+            @parse $header [$($units)* [$key_value; $case_name]] $tuples $structs $objects
+            $($($rest)*)?
+        }
+    };
+    (@case $header:tt $units:tt [$($tuples:tt)*] $structs:tt $objects:tt
+        $key_value:tt $case_name:ident($field_type:ty) $(, $($rest:tt)*)?) => {
+        union_formatter! {
+            @parse $header $units [$($tuples)* [$key_value; $case_name($field_type)]] $structs $objects
+            $($($rest)*)?
+        }
+    };
+    (@case $header:tt $units:tt $tuples:tt [$($structs:tt)*] $objects:tt
+        $key_value:tt $case_name:ident { $($field_name:ident: $field_type:ty),* $(,)? } $(, $($rest:tt)*)?) => {
+        union_formatter! {
+            @parse $header $units $tuples [$($structs)* [$key_value; $case_name { $($field_name: $field_type),* }]] $objects
+            $($($rest)*)?
+        }
+    };
+    (@case $header:tt $units:tt $tuples:tt $structs:tt [$($objects:tt)*]
+        $key_value:tt $case_name:ident { $($index:expr; $field_name:ident: $field_type:ty),* $(,)? } $(, $($rest:tt)*)?) => {
+        union_formatter! {
+            @parse $header $units $tuples $structs [$($objects)* [$key_value; $case_name { $($index; $field_name: $field_type),* }]]
+            $($($rest)*)?
+        }
+    };
+    (@unknown [] $name:ident, $this:tt, $key:ident, $offset:ident, $start_offset:ident, $end_offset:ident) => {
+        return ZeroFormatterError::invalid($crate::ErrorKind::UnknownUnionKey, $start_offset + 4)
+    };
+    (@unknown [$fallback:ident] $name:ident, $this:tt, $key:ident, $offset:ident, $start_offset:ident, $end_offset:ident) => {
+        $name::$fallback($key, try!(util::read_bytes($this, *$offset, $end_offset - *$offset)))
+    };
+    (@impl [$buffer:ty; $name:ident; $key_type:ty; [$($fallback:ident)*]; [$($derive:ident),*]]
+        [$([[$($unit_key:tt)*]; $unit_case:ident])*]
+        [$([[$($tuple_key:tt)*]; $tuple_case:ident($tuple_type:ty)])*]
+        [$([[$($struct_key:tt)*]; $struct_case:ident { $($struct_field:ident: $struct_field_type:ty),* }])*]
+        [$([[$($object_key:tt)*]; $object_case:ident { $($object_index:expr; $object_field:ident: $object_field_type:ty),* }])*]
+    ) => {
+        #[derive($($derive),*)]
         pub enum $name {
-            $($case_name($field_type)),*
+            $($unit_case,)*
+            $($tuple_case($tuple_type),)*
+            $($struct_case { $($struct_field: $struct_field_type),* },)*
+            $($object_case { $($object_field: $object_field_type),* },)*
+            $($fallback($key_type, Vec<u8>))*
         }
 
         impl Formatter<$name> for $buffer {
@@ -65,11 +289,49 @@ macro_rules! union_formatter {
 
                 match value {
                     $(
-                    $name::$case_name(v) => {
-                        byte_size += try!(self.serialize(offset + (byte_size as u64), $key_value));
-                        byte_size += try!(self.serialize(offset + (byte_size as u64), v))
+                    $name::$unit_case => {
+                        let key = <$key_type as $crate::UnionKey>::from_literal($($unit_key)*);
+                        byte_size += try!(<Self as Formatter<$key_type>>::serialize(self, offset + (byte_size as u64), key));
+                    }
+                    )*
+                    $(
+                    $name::$tuple_case(v) => {
+                        let key = <$key_type as $crate::UnionKey>::from_literal($($tuple_key)*);
+                        byte_size += try!(<Self as Formatter<$key_type>>::serialize(self, offset + (byte_size as u64), key));
+                        byte_size += try!(self.serialize(offset + (byte_size as u64), v));
+                    }
+                    )*
+                    $(
+                    $name::$struct_case { $($struct_field),* } => {
+                        let key = <$key_type as $crate::UnionKey>::from_literal($($struct_key)*);
+                        byte_size += try!(<Self as Formatter<$key_type>>::serialize(self, offset + (byte_size as u64), key));
+                        $(
+                        byte_size += try!(self.serialize(offset + (byte_size as u64), $struct_field));
+                        )*
+                    }
+                    )*
+                    $(
+                    $name::$object_case { $($object_field),* } => {
+                        let key = <$key_type as $crate::UnionKey>::from_literal($($object_key)*);
+                        byte_size += try!(<Self as Formatter<$key_type>>::serialize(self, offset + (byte_size as u64), key));
+                        let start_offset = offset + (byte_size as u64);
+                        let last_index: i32 = *([$($object_index),*].iter().max().unwrap());
+                        let mut object_size: i32 = 4 + 4 + 4 * (last_index + 1);
+                        try!(self.serialize(start_offset + 4, last_index));
+                        $(
+                        try!(self.serialize(start_offset + 4 + 4 + 4 * $object_index, (start_offset as i32) + object_size));
+                        object_size += try!(self.serialize(start_offset + (object_size as u64), $object_field));
+                        )*
+                        try!(self.serialize(start_offset, object_size));
+                        byte_size += object_size;
                     }
-                    ),*
+                    )*
+                    $(
+                    $name::$fallback(key, bytes) => {
+                        byte_size += try!(<Self as Formatter<$key_type>>::serialize(self, offset + (byte_size as u64), key));
+                        byte_size += try!(util::write_bytes(self, offset + (byte_size as u64), &bytes));
+                    }
+                    )*
                 }
 
                 try!(self.serialize(offset, byte_size));
@@ -78,18 +340,103 @@ macro_rules! union_formatter {
             }
 
             fn deserialize(&mut self, offset: &mut u64) -> ZeroFormatterResult<$name> {
-
-                try!(util::check_non_null(self, offset));
+                let start_offset = *offset;
+                let byte_size = try!(util::check_non_null(self, offset));
+                let end_offset = start_offset + (byte_size as u64);
 
                 let key: $key_type = try!(self.deserialize(offset));
-                match key {
+                if *offset > end_offset {
+                    let size_mismatch = $crate::ErrorKind::SizeMismatch { expected: byte_size as u64, actual: *offset - start_offset };
+                    return ZeroFormatterError::invalid(size_mismatch, start_offset);
+                }
+                #[deny(unreachable_patterns)]
+                let value = match <$key_type as $crate::UnionKey>::as_pattern(&key) {
+                    $(
+                    $($unit_key)* => $name::$unit_case,
+                    )*
+                    $(
+                    $($tuple_key)* => {
+                        let v: $tuple_type = try!(self.deserialize(offset)
+                            .map_err(|e| e.in_field(stringify!($name), stringify!($tuple_case))));
+                        $name::$tuple_case(v)
+                    }
+                    )*
+                    $(
+                    $($struct_key)* => {
+                        $(
+                        let $struct_field: $struct_field_type = try!(self.deserialize(offset)
+                            .map_err(|e| e.in_field(stringify!($struct_case), stringify!($struct_field))
+                                .in_field(stringify!($name), stringify!($struct_case))));
+                        )*
+                        $name::$struct_case { $($struct_field),* }
+                    }
+                    )*
+                    $(
+                    $($object_key)* => {
+                        let object_offset = *offset;
+                        try!(util::check_non_null(self, offset));
+                        let last_index: i32 = try!(self.deserialize(offset));
+                        $(
+                        let $object_field: $object_field_type = try!(util::deserialize_object_field(self, object_offset, last_index, $object_index)
+                            .map_err(|e| e.in_field(stringify!($object_case), stringify!($object_field))
+                                .in_field(stringify!($name), stringify!($object_case))));
+                        )*
+                        $name::$object_case { $($object_field),* }
+                    }
+                    )*
+                    #[allow(unreachable_patterns)]
+                    _ => union_formatter!(@unknown [$($fallback)*] $name, self, key, offset, start_offset, end_offset)
+                };
+
+                *offset = end_offset;
+                Ok(value)
+            }
+
+            fn serialized_size(value: &$name) -> ZeroFormatterResult<i32> {
+                match *value {
+                    $(
+                    $name::$unit_case => {
+                        let key = <$key_type as $crate::UnionKey>::from_literal($($unit_key)*);
+                        let key = try!(<Self as Formatter<$key_type>>::serialized_size(&key));
+                        Ok(4 + key)
+                    }
+                    )*
                     $(
-                    $key_value => {
-                        let v: $field_type = try!(self.deserialize(offset));
-                        Ok( $name::$case_name (v) )
+                    $name::$tuple_case(ref v) => {
+                        let key = <$key_type as $crate::UnionKey>::from_literal($($tuple_key)*);
+                        let key = try!(<Self as Formatter<$key_type>>::serialized_size(&key));
+                        let v = try!(<Self as Formatter<$tuple_type>>::serialized_size(v));
+                        Ok(4 + key + v)
                     }
-                    ),*,
-                    _ => ZeroFormatterError::invalid_binary(*offset)
+                    )*
+                    $(
+                    $name::$struct_case { $(ref $struct_field),* } => {
+                        let key = <$key_type as $crate::UnionKey>::from_literal($($struct_key)*);
+                        let mut byte_size = 4 + try!(<Self as Formatter<$key_type>>::serialized_size(&key));
+                        $(
+                        byte_size += try!(<Self as Formatter<$struct_field_type>>::serialized_size($struct_field));
+                        )*
+                        Ok(byte_size)
+                    }
+                    )*
+                    $(
+                    $name::$object_case { $(ref $object_field),* } => {
+                        let last_index: i32 = *([$($object_index),*].iter().max().unwrap());
+                        let key = <$key_type as $crate::UnionKey>::from_literal($($object_key)*);
+                        let mut byte_size = 4 + try!(<Self as Formatter<$key_type>>::serialized_size(&key));
+                        byte_size += 4 + 4 + 4 * (last_index + 1);
+                        $(
+                        byte_size += try!(<Self as Formatter<$object_field_type>>::serialized_size($object_field));
+                        )*
+                        Ok(byte_size)
+                    }
+                    )*
+                    $(
+                    $name::$fallback(ref key, ref bytes) => {
+                        let key = try!(<Self as Formatter<$key_type>>::serialized_size(key));
+                        Ok(4 + key + bytes.len() as i32)
+                    }
+                    )*
                 }
             }
         }
@@ -105,7 +452,7 @@ macro_rules! union_formatter {
 mod tests {
 
     use std::io::Cursor;
-    use std::io::{Seek, SeekFrom};
+    use std::borrow::Cow;
     use error::*;
     use formatter::*;
     use util;
@@ -149,4 +496,183 @@ mod tests {
         let mut offset = 0;
         assert_eq!(input, c.deserialize(&mut offset).unwrap());
     }
+
+    #[test]
+    fn serialized_size_union() {
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<U>>::serialized_size(&U::A(O { a: 1 })).unwrap(), 24);
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<U>>::serialized_size(&U::B(S { b: 2 })).unwrap(), 16);
+        let none: Option<U> = None;
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<Option<U>>>::serialized_size(&none).unwrap(), 4);
+    }
+
+    union_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        #[fallback(Unknown)]
+        enum F: i32 {
+            1; A(O)
+        }
+    }
+
+    #[test]
+    fn deserialize_union_fallback() {
+        let mut c = Cursor::new(Vec::new());
+        c.serialize(0, U::B(S { b: 2 })).unwrap();
+        c.serialize(16, 3i32).unwrap();
+        let mut offset = 0;
+        let actual: F = c.deserialize(&mut offset).unwrap();
+        assert_eq!(F::Unknown(2, vec![2, 0, 0, 0, 0, 0, 0, 0]), actual);
+        assert_eq!(offset, 16);
+        let next: i32 = c.deserialize(&mut offset).unwrap();
+        assert_eq!(next, 3);
+    }
+
+    #[test]
+    fn serialize_deserialize_union_fallback() {
+        let mut c = Cursor::new(Vec::new());
+        let input = F::Unknown(2, vec![2, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<F>>::serialized_size(&input).unwrap(), 16);
+        assert_eq!(c.serialize(0, input.clone()).unwrap(), 16);
+        let mut offset = 0;
+        let actual: U = c.deserialize(&mut offset).unwrap();
+        assert_eq!(U::B(S { b: 2 }), actual);
+        let mut offset = 0;
+        assert_eq!(input, c.deserialize(&mut offset).unwrap());
+    }
+
+    #[test]
+    fn deserialize_union_fallback_known() {
+        let mut c = Cursor::new(Vec::new());
+        assert_eq!(c.serialize(0, F::A(O { a: 1 })).unwrap(), 24);
+        let mut offset = 0;
+        assert_eq!(F::A(O { a: 1 }), c.deserialize(&mut offset).unwrap());
+        assert_eq!(offset, 24);
+    }
+
+    #[test]
+    fn deserialize_union_fallback_invalid_byte_size() {
+        let mut c = Cursor::new(vec![2, 0, 0, 0, 2, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<F> = c.deserialize(&mut offset);
+        assert_eq!(actual.unwrap_err().kind(), Some(ErrorKind::SizeMismatch { expected: 2, actual: 8 }));
+    }
+
+    #[test]
+    fn deserialize_union_fallback_oversized_byte_size() {
+        // byteSize 0x7ffffff0 is not allocated, because the buffer has only 8 bytes after the key.
+        let bytes = vec![0xf0, 0xff, 0xff, 0x7f, 2, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0];
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<F> = Cursor::new(bytes).deserialize(&mut offset);
+        let e = actual.unwrap_err();
+        assert_eq!(e.kind(), Some(ErrorKind::SizeMismatch { expected: 0x7ffffff0 - 8, actual: 8 }));
+        assert_eq!(e.offset(), Some(8));
+    }
+
+    #[test]
+    fn deserialize_union_unknown_key() {
+        let mut c = Cursor::new(vec![16, 0, 0, 0, 3, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<U> = c.deserialize(&mut offset);
+        assert_eq!(actual.unwrap_err().to_string(), "[offset 4] unknown union key");
+    }
+
+    union_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        enum C: i32 {
+            1; Ping,
+            2; Move { x: i32, y: i32 },
+            3; Say { 0; volume: i32, 2; pitch: i64 },
+            4; Other(S)
+        }
+    }
+
+    #[test]
+    fn serialize_union_unit() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, C::Ping).unwrap(), 8);
+        assert_eq!(wtr.into_inner(), vec![8, 0, 0, 0, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serialize_union_struct_variant() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, C::Move { x: 1, y: -1 }).unwrap(), 16);
+        assert_eq!(wtr.into_inner(), vec![16, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn deserialize_union_struct_variant_error_path() {
+        let mut rdr = Cursor::new(vec![16, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0]);
+        let mut offset = 0;
+        let actual: ZeroFormatterResult<C> = rdr.deserialize(&mut offset);
+        assert_eq!(actual.unwrap_err().path().unwrap().to_string(), "C.Move.y");
+    }
+
+    #[test]
+    fn serialize_union_object_variant() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, C::Say { volume: 1, pitch: 2 }).unwrap(), 40);
+        assert_eq!(wtr.into_inner(), vec![
+            40, 0, 0, 0, 3, 0, 0, 0,
+            32, 0, 0, 0, 2, 0, 0, 0, 28, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0,
+            1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn deserialize_union_variants() {
+        let inputs = vec![C::Ping, C::Move { x: 1, y: 2 }, C::Say { volume: 3, pitch: 4 }, C::Other(S { b: 5 })];
+        for input in inputs {
+            let mut c = Cursor::new(Vec::new());
+            let byte_size = c.serialize(0, input).unwrap();
+            assert_eq!(<Cursor<Vec<u8>> as Formatter<C>>::serialized_size(&input).unwrap(), byte_size);
+            let mut offset = 0;
+            assert_eq!(input, c.deserialize(&mut offset).unwrap());
+            assert_eq!(offset, byte_size as u64);
+        }
+    }
+
+    #[test]
+    fn deserialize_union_object_variant_versioning() {
+        // Say written by an older writer which has only volume.
+        let mut rdr = Cursor::new(vec![
+            24, 0, 0, 0, 3, 0, 0, 0,
+            16, 0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0,
+            7, 0, 0, 0]);
+        let mut offset = 0;
+        assert_eq!(C::Say { volume: 7, pitch: 0 }, rdr.deserialize(&mut offset).unwrap());
+        assert_eq!(offset, 24);
+    }
+
+    union_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        enum N: Cow<'static, str> {
+            "a"; A(i32),
+            "bc"; B
+        }
+    }
+
+    union_formatter! {
+        #[target(Cursor<Vec<u8>>)]
+        #[fallback(Unknown)]
+        enum NS: String {
+            "a"; A(i32)
+        }
+    }
+
+    #[test]
+    fn serialize_union_string_key() {
+        let mut wtr = Cursor::new(Vec::new());
+        assert_eq!(wtr.serialize(0, N::A(1)).unwrap(), 13);
+        assert_eq!(wtr.into_inner(), vec![13, 0, 0, 0, 1, 0, 0, 0, 0x61, 1, 0, 0, 0]);
+        assert_eq!(<Cursor<Vec<u8>> as Formatter<N>>::serialized_size(&N::B).unwrap(), 10);
+    }
+
+    #[test]
+    fn deserialize_union_string_key() {
+        let mut rdr = Cursor::new(vec![10, 0, 0, 0, 2, 0, 0, 0, 0x62, 0x63]);
+        let mut offset = 0;
+        assert_eq!(N::B, rdr.deserialize(&mut offset).unwrap());
+        let mut offset = 0;
+        let actual: NS = rdr.deserialize(&mut offset).unwrap();
+        assert_eq!(NS::Unknown("bc".to_string(), vec![]), actual);
+    }
 }
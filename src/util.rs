@@ -1,15 +1,114 @@
 use error::*;
 use formatter::*;
 
-use std::io::Seek;
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use buffer::ByteBuffer;
 
 pub fn check_non_null<R>(r: &mut R, offset: &mut u64) -> ZeroFormatterResult<i32>
-    where R: Seek + ReadBytesExt + WriteBytesExt + Formatter<i32> {
+    where R: ByteBuffer + Formatter<i32> {
     r.deserialize(offset)
         .and_then(|bs| if bs >= 0 {
             Ok(bs)
         } else {
-            ZeroFormatterError::invalid_binary(*offset)
+            ZeroFormatterError::invalid(ErrorKind::NegativeLength(bs), *offset - 4)
         })
 }
+
+/// Read `len` raw bytes at `offset`.
+/// `len` is checked against the buffer before allocating, because it may come from the binary.
+pub fn read_bytes<R>(r: &mut R, offset: u64, len: u64) -> ZeroFormatterResult<Vec<u8>>
+    where R: ByteBuffer {
+    let available = try!(r.byte_len()).saturating_sub(offset);
+    if len > available {
+        return ZeroFormatterError::invalid(ErrorKind::SizeMismatch { expected: len, actual: available }, offset);
+    }
+    let mut bytes = vec![0; len as usize];
+    try!(r.read_at(offset, &mut bytes));
+    Ok(bytes)
+}
+
+/// Write raw bytes at `offset`, and return the number of bytes.
+pub fn write_bytes<R>(r: &mut R, offset: u64, bytes: &[u8]) -> ZeroFormatterResult<i32>
+    where R: ByteBuffer {
+    try!(r.write_at(offset, bytes));
+    Ok(bytes.len() as i32)
+}
+
+/// Return the offset of the field at `index` in Object format which starts at `start_offset`.
+/// Return `None` if the field is not written.
+pub fn object_field_offset<R>(r: &mut R, start_offset: u64, last_index: i32, index: i32) -> ZeroFormatterResult<Option<u64>>
+    where R: ByteBuffer + Formatter<i32> {
+    if index > last_index {
+        return Ok(None);
+    }
+    let mut o = start_offset + 4 + 4 + 4 * (index as u64);
+    let field_offset: i32 = try!(r.deserialize(&mut o));
+    if field_offset == 0 {
+        Ok(None)
+    } else if field_offset < 0 {
+        ZeroFormatterError::invalid(ErrorKind::OffsetOutOfRange(field_offset), o - 4)
+    } else {
+        Ok(Some(field_offset as u64))
+    }
+}
+
+/// Deserialize the field at `index` in Object format which starts at `start_offset`.
+/// Return the default value if the field is not written.
+pub fn deserialize_object_field<R, T>(r: &mut R, start_offset: u64, last_index: i32, index: i32) -> ZeroFormatterResult<T>
+    where R: ByteBuffer + Formatter<i32> + Formatter<T>, T: Default {
+    match try!(object_field_offset(r, start_offset, last_index, index)) {
+        Some(mut o) => r.deserialize(&mut o),
+        None => Ok(Default::default())
+    }
+}
+
+/// Return `len` as the length of the format, or `IntegerOverflow` error.
+pub fn length_to_i32(len: usize, offset: u64) -> ZeroFormatterResult<i32> {
+    if len > i32::MAX as usize {
+        ZeroFormatterError::invalid(ErrorKind::IntegerOverflow, offset)
+    } else {
+        Ok(len as i32)
+    }
+}
+
+/// Overwrite the field at `index` in Object format which starts at `start_offset`.
+/// The field must be written, and `T` must be fixed length format with the same byte size as the field.
+/// The header and the offset of the field are checked against `byteSize` before anything is written.
+/// It works with `Cursor<&mut [u8]>` too.
+pub fn patch_object_field<R, T>(r: &mut R, start_offset: u64, index: i32, value: T) -> ZeroFormatterResult<()>
+    where R: ByteBuffer + Formatter<i32> + Formatter<T> {
+    let length = match <R as Formatter<T>>::length() {
+        Some(l) => l as u64,
+        None => return Err(ZeroFormatterError::InvalidValue(ErrorKind::NotFixedLength))
+    };
+    let mut o = start_offset;
+    let byte_size = try!(check_non_null(r, &mut o));
+    let last_index: i32 = try!(r.deserialize(&mut o));
+    let header_size = 4 + 4 + 4 * (last_index as i64 + 1);
+    if last_index < -1 || header_size > byte_size as i64 {
+        return ZeroFormatterError::invalid(ErrorKind::OffsetOutOfRange(last_index), start_offset + 4);
+    }
+    if index < 0 {
+        return ZeroFormatterError::invalid(ErrorKind::MissingField(index), start_offset);
+    }
+    let field_offset = match try!(object_field_offset(r, start_offset, last_index, index)) {
+        Some(fo) => fo,
+        None => return ZeroFormatterError::invalid(ErrorKind::MissingField(index), start_offset)
+    };
+    let mut end = start_offset + (byte_size as u64);
+    if field_offset < start_offset + (header_size as u64) || field_offset >= end {
+        return ZeroFormatterError::invalid(ErrorKind::OffsetOutOfRange(field_offset as i32), start_offset + 4 + 4 + 4 * (index as u64));
+    }
+    for i in 0..(last_index + 1) {
+        if let Some(fo) = try!(object_field_offset(r, start_offset, last_index, i)) {
+            if fo > field_offset && fo < end {
+                end = fo;
+            }
+        }
+    }
+    if end - field_offset != length {
+        return ZeroFormatterError::invalid(ErrorKind::SizeMismatch { expected: end - field_offset, actual: length }, field_offset);
+    }
+    try!(r.serialize(field_offset, value));
+    Ok(())
+}
+
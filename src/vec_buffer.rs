@@ -0,0 +1,224 @@
+use error::*;
+use formatter::*;
+use buffer::{ByteBuffer, MemoryBuffer};
+
+use std::io;
+use std::borrow::Cow;
+
+/// `VecBuffer` is an in-memory buffer which reads and writes bytes by index, without seeking.
+/// It borrows the bytes which it reads, and copies them only when it is written.
+///
+/// `to_vec` and `from_slice` use it for formats which this crate provides.
+/// Because of orphan rules, user types target a buffer declared by `declare_buffer` over `VecBuffer<'_>`.
+///
+/// ```
+/// # #[macro_use] extern crate zero_formatter;
+/// # use zero_formatter::*;
+/// # use std::io::Result;
+/// #
+/// declare_buffer! { Buffer }
+///
+/// object_formatter! {
+///     #[target(Buffer<VecBuffer<'_>>)]
+///     ObjectSample {
+///         0; a: i32,
+///         1; b: i64
+///     }
+/// }
+///
+/// # fn example() -> Result<()> {
+/// let bytes = try!(to_vec::<Buffer<VecBuffer>, _>(ObjectSample { a: 1, b: 2 }));
+/// let actual: ObjectSample = try!(from_slice::<Buffer<VecBuffer>, _>(&bytes));
+/// assert_eq!(ObjectSample { a: 1, b: 2 }, actual);
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// # example().unwrap();
+/// # }
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct VecBuffer<'a> {
+    bytes: Cow<'a, [u8]>
+}
+
+impl<'a> VecBuffer<'a> {
+
+    pub fn new(bytes: Vec<u8>) -> VecBuffer<'a> {
+        VecBuffer { bytes: Cow::Owned(bytes) }
+    }
+
+    pub fn with_capacity(capacity: usize) -> VecBuffer<'a> {
+        VecBuffer::new(Vec::with_capacity(capacity))
+    }
+
+    pub fn get_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.bytes.into_owned()
+    }
+}
+
+/// Return the range of `len` bytes at `offset`, or `None` if it overflows.
+fn range(offset: u64, len: usize) -> Option<(usize, usize)> {
+    if offset > usize::MAX as u64 {
+        return None;
+    }
+    let start = offset as usize;
+    start.checked_add(len).map(|end| (start, end))
+}
+
+impl<'a> ByteBuffer for VecBuffer<'a> {
+
+    #[inline]
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        match range(offset, buf.len()) {
+            Some((start, end)) if end <= self.bytes.len() => {
+                buf.copy_from_slice(&self.bytes[start..end]);
+                Ok(())
+            },
+            _ => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer"))
+        }
+    }
+
+    #[inline]
+    fn write_at(&mut self, offset: u64, buf: &[u8]) -> io::Result<()> {
+        let (start, end) = match range(offset, buf.len()) {
+            Some(r) => r,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "write to an overflowing position"))
+        };
+        let bytes = self.bytes.to_mut();
+        if end > bytes.len() {
+            bytes.resize(end, 0);
+        }
+        bytes[start..end].copy_from_slice(buf);
+        Ok(())
+    }
+
+    #[inline]
+    fn byte_len(&mut self) -> io::Result<u64> {
+        Ok(self.bytes.len() as u64)
+    }
+}
+
+impl<'a> MemoryBuffer<'a> for VecBuffer<'a> {
+
+    fn from_vec(bytes: Vec<u8>) -> VecBuffer<'a> {
+        VecBuffer::new(bytes)
+    }
+
+    fn from_slice(bytes: &'a [u8]) -> VecBuffer<'a> {
+        VecBuffer { bytes: Cow::Borrowed(bytes) }
+    }
+
+    fn into_vec(self) -> Vec<u8> {
+        self.into_inner()
+    }
+}
+
+/// Serialize `value` into a new `Vec<u8>` through the buffer `B`, such as `VecBuffer`.
+pub fn to_vec<B, T>(value: T) -> ZeroFormatterResult<Vec<u8>> where B: MemoryBuffer<'static> + Formatter<T> {
+    let capacity = <B as Formatter<T>>::length().unwrap_or(0);
+    let mut buffer = B::from_vec(Vec::with_capacity(capacity as usize));
+    try!(buffer.serialize(0, value));
+    Ok(buffer.into_vec())
+}
+
+/// Return byte size which `to_vec` writes for `value`.
+pub fn serialized_size<B, T>(value: &T) -> ZeroFormatterResult<i32> where B: Formatter<T> {
+    <B as Formatter<T>>::serialized_size(value)
+}
+
+/// Deserialize `T` from the head of `bytes` through the buffer `B`, such as `VecBuffer`.
+/// `bytes` is borrowed, not copied.
+pub fn from_slice<'a, B, T>(bytes: &'a [u8]) -> ZeroFormatterResult<T> where B: MemoryBuffer<'a> + Formatter<T> {
+    let mut buffer = B::from_slice(bytes);
+    let mut offset = 0;
+    buffer.deserialize(&mut offset)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::borrow::Cow;
+    use error::*;
+    use formatter::*;
+    use buffer::*;
+    use vec_buffer::*;
+    use util;
+
+    declare_buffer! { Buffer }
+
+    object_formatter! {
+        #[target(Buffer<VecBuffer<'_>>)]
+        O {
+            0; a: i32,
+            1; b: Option<i64>
+        }
+    }
+
+    #[test]
+    fn serialize_i32() {
+        assert_eq!(to_vec::<VecBuffer, _>(1i32).unwrap(), vec![1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn deserialize_i32() {
+        assert_eq!(from_slice::<VecBuffer, i32>(&[1, 0, 0, 0]).unwrap(), 1);
+    }
+
+    #[test]
+    fn deserialize_out_of_range() {
+        let actual: ZeroFormatterResult<i64> = from_slice::<VecBuffer, _>(&[1, 0, 0, 0]);
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn serialize_deserialize_vec() {
+        let input: Vec<Cow<'static, str>> = vec![Cow::Borrowed("a"), Cow::Borrowed("bc")];
+        let bytes = to_vec::<VecBuffer, _>(input.clone()).unwrap();
+        let actual: Vec<Cow<'static, str>> = from_slice::<VecBuffer, _>(&bytes).unwrap();
+        assert_eq!(input, actual);
+    }
+
+    #[test]
+    fn serialize_deserialize_object() {
+        let input = O { a: 1, b: Some(2) };
+        let bytes = to_vec::<Buffer<VecBuffer>, _>(input).unwrap();
+        assert_eq!(bytes.len() as i32, serialized_size::<Buffer<VecBuffer>, _>(&input).unwrap());
+        assert_eq!(input, from_slice::<Buffer<VecBuffer>, O>(&bytes).unwrap());
+        let actual: Option<O> = from_slice::<Buffer<VecBuffer>, _>(&[0xff, 0xff, 0xff, 0xff]).unwrap();
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn write_after_end() {
+        let mut b = VecBuffer::default();
+        b.write_at(2, &[1, 2]).unwrap();
+        b.write_at(0, &[3]).unwrap();
+        assert_eq!(b.get_ref(), &[3, 0, 1, 2]);
+        assert!(b.write_at(u64::MAX, &[1]).is_err());
+        let mut buf = [0; 2];
+        assert!(b.read_at(3, &mut buf).is_err());
+        b.read_at(2, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(b.byte_len().unwrap(), 4);
+    }
+
+    #[test]
+    fn write_borrowed() {
+        let bytes = [1, 2, 3];
+        let mut b = VecBuffer::from_slice(&bytes);
+        b.write_at(1, &[4]).unwrap();
+        assert_eq!(b.into_vec(), vec![1, 4, 3]);
+        assert_eq!(bytes, [1, 2, 3]);
+    }
+
+    #[test]
+    fn serialized_size_vec() {
+        let input: Vec<Cow<'static, str>> = vec![Cow::Borrowed("a"), Cow::Borrowed("bc")];
+        assert_eq!(serialized_size::<VecBuffer, _>(&input).unwrap() as usize, to_vec::<VecBuffer, _>(input).unwrap().len());
+    }
+}
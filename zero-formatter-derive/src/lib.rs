@@ -0,0 +1,174 @@
+//! Custom derive for [zero-formatter](https://github.com/pocketberserker/zero-formatter.rs).
+//!
+//! `#[derive(ZeroFormatter)]` provide Object format formatter for an existing struct.
+//! The generated code is the same as `object_formatter` macro, so the struct must implement `Default`.
+//! `#[zero(view = "Name")]` also defines a lazy view like `#[view(Name)]` of `object_formatter`, so the target must be a `MemoryBuffer`.
+//! `#[zero(tracked)]` also provides formatter of `Tracked<T>`, so the struct must implement `Clone` and fields must implement `PartialEq`.
+//!
+//! ```
+//! #[macro_use] extern crate zero_formatter;
+//! #[macro_use] extern crate zero_formatter_derive;
+//! use zero_formatter::*;
+//! use std::io::{Cursor, Result};
+//!
+//! declare_buffer! { Buffer }
+//!
+//! #[derive(ZeroFormatter, Default, Debug, PartialEq)]
+//! #[zero(target = "Buffer<Cursor<Vec<u8>>>")]
+//! pub struct ObjectSample {
+//!     #[zero(index = 0)]
+//!     a: i32,
+//!     #[zero(index = 1)]
+//!     b: i64
+//! }
+//!
+//! # fn main() {
+//! let mut writer = Buffer::new(Cursor::new(Vec::new()));
+//! writer.serialize(0, ObjectSample { a: 1, b: 2 }).unwrap();
+//! # }
+//! ```
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span};
+use syn::{Data, DeriveInput, Error, Fields, Ident, LitInt, LitStr, Type};
+
+#[proc_macro_derive(ZeroFormatter, attributes(zero))]
+pub fn derive_zero_formatter(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match expand_object(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+struct Field {
+    index: i32,
+    name: Ident,
+    ty: Type
+}
+
+fn expand_object(input: &DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "ZeroFormatter does not support generic types"));
+    }
+
+    let (target, view, tracked) = try!(parse_struct_attrs(input));
+
+    let named = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(Error::new_spanned(&input.ident, "ZeroFormatter supports only structs with named fields"))
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "ZeroFormatter supports only structs"))
+    };
+
+    let mut fields: Vec<Field> = Vec::with_capacity(named.len());
+    for field in named {
+        let name = field.ident.clone().unwrap();
+        let index = try!(parse_index(field));
+        if let Some(other) = fields.iter().find(|f| f.index == index) {
+            return Err(Error::new_spanned(
+                field,
+                format!("index {} is already used by field `{}`", index, other.name)));
+        }
+        fields.push(Field { index, name, ty: field.ty.clone() });
+    }
+
+    if fields.is_empty() {
+        return Err(Error::new_spanned(&input.ident, "ZeroFormatter requires at least one field"));
+    }
+
+    let name = &input.ident;
+    let indexes: Vec<Literal> = fields.iter().map(|f| Literal::i32_unsuffixed(f.index)).collect();
+    let field_names: Vec<&Ident> = fields.iter().map(|f| &f.name).collect();
+    let field_types: Vec<&Type> = fields.iter().map(|f| &f.ty).collect();
+
+    let view = view.map(|view| quote! {
+        object_view! {
+            #[target(#target)]
+            #view {
+                #(#indexes; #field_names: #field_types),*
+            }
+        }
+    });
+
+    let tracked = if tracked {
+        Some(quote! {
+            tracked_formatter! {
+                #[target(#target)]
+                #name {
+                    #(#indexes; #field_names: #field_types),*
+                }
+            }
+        })
+    } else {
+        None
+    };
+
+    Ok(quote! {
+        object_formatter_impl! {
+            #[target(#target)]
+            #name {
+                #(#indexes; #field_names: #field_types),*
+            }
+        }
+
+        #view
+
+        #tracked
+    })
+}
+
+fn parse_struct_attrs(input: &DeriveInput) -> Result<(Type, Option<Ident>, bool), Error> {
+    let mut target: Option<Type> = None;
+    let mut view: Option<Ident> = None;
+    let mut tracked = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("zero")) {
+        try!(attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("target") {
+                let s: LitStr = try!(try!(meta.value()).parse());
+                target = Some(try!(s.parse()));
+                Ok(())
+            } else if meta.path.is_ident("view") {
+                let s: LitStr = try!(try!(meta.value()).parse());
+                view = Some(try!(s.parse()));
+                Ok(())
+            } else if meta.path.is_ident("tracked") {
+                tracked = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported zero attribute"))
+            }
+        }));
+    }
+    let target = try!(target.ok_or_else(|| Error::new(
+        Span::call_site(),
+        "ZeroFormatter requires #[zero(target = \"...\")] attribute")));
+    Ok((target, view, tracked))
+}
+
+fn parse_index(field: &syn::Field) -> Result<i32, Error> {
+    let mut index: Option<i32> = None;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("zero")) {
+        try!(attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("index") {
+                let i: LitInt = try!(try!(meta.value()).parse());
+                let i: i32 = try!(i.base10_parse());
+                if i < 0 {
+                    return Err(meta.error("index must not be negative"));
+                }
+                index = Some(i);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported zero attribute"))
+            }
+        }));
+    }
+    index.ok_or_else(|| Error::new_spanned(field, "field requires #[zero(index = N)] attribute"))
+}
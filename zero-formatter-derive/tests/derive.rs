@@ -0,0 +1,122 @@
+extern crate zero_formatter;
+#[macro_use]
+extern crate zero_formatter_derive;
+
+use zero_formatter::*;
+use std::io::{Cursor, Result};
+
+declare_buffer! { Buffer }
+
+/// Documented struct with its own derives.
+#[derive(ZeroFormatter, Default, Debug, PartialEq, Clone)]
+#[zero(target = "Buffer<Cursor<Vec<u8>>>")]
+pub struct O {
+    #[zero(index = 0)]
+    a: i32,
+    #[zero(index = 1)]
+    b: i64
+}
+
+#[derive(ZeroFormatter, Default, Debug, PartialEq)]
+#[zero(target = "Buffer<Cursor<Vec<u8>>>")]
+pub struct O2 {
+    #[zero(index = 0)]
+    a: i32,
+    #[zero(index = 1)]
+    b: i64,
+    #[zero(index = 2)]
+    c: i8
+}
+
+#[derive(ZeroFormatter, Default, Debug, PartialEq)]
+#[zero(target = "Buffer<Cursor<Vec<u8>>>")]
+pub struct Skip {
+    #[zero(index = 1)]
+    b: i64,
+    #[zero(index = 0)]
+    a: i32
+}
+
+#[test]
+fn serialize_object() {
+    let mut wtr = Buffer::new(Cursor::new(Vec::new()));
+    assert_eq!(wtr.serialize(0, O { a: 1, b: 2 }).unwrap(), 28);
+    assert_eq!(wtr.inner.into_inner(), vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn deserialize_object() {
+    let mut rdr = Buffer::new(Cursor::new(vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]));
+    let mut offset = 0;
+    assert_eq!(O { a: 1, b: 2 }, rdr.deserialize(&mut offset).unwrap());
+    assert_eq!(offset, 28);
+}
+
+#[test]
+fn serialize_object_declared_out_of_index_order() {
+    let mut wtr = Buffer::new(Cursor::new(Vec::new()));
+    assert_eq!(wtr.serialize(0, Skip { a: 1, b: 2 }).unwrap(), 28);
+    assert_eq!(wtr.inner.into_inner(), vec![28, 0, 0, 0, 1, 0, 0, 0, 24, 0, 0, 0, 16, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+}
+
+#[test]
+fn deserialize_object_versioning() {
+    let mut rdr = Buffer::new(Cursor::new(vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]));
+    let mut offset = 0;
+    assert_eq!(O2 { a: 1, b: 2, c: 0 }, rdr.deserialize(&mut offset).unwrap());
+}
+
+#[test]
+fn serialize_object_none() {
+    let mut wtr = Buffer::new(Cursor::new(Vec::new()));
+    let input: Option<O> = None;
+    assert_eq!(wtr.serialize(0, input).unwrap(), 4);
+    assert_eq!(wtr.inner.into_inner(), vec![0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn deserialize_object_some() {
+    let mut rdr = Buffer::new(Cursor::new(vec![28, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]));
+    let mut offset = 0;
+    assert_eq!(Some(O { a: 1, b: 2 }), rdr.deserialize(&mut offset).unwrap());
+}
+
+#[derive(ZeroFormatter, Default, Debug, PartialEq)]
+#[zero(target = "Buffer<VecBuffer<'_>>", view = "VView")]
+pub struct V {
+    #[zero(index = 0)]
+    a: i32,
+    #[zero(index = 1)]
+    b: i64
+}
+
+#[test]
+fn object_view() {
+    let bytes = to_vec::<Buffer<VecBuffer>, _>(V { a: 1, b: 2 }).unwrap();
+    let mut offset = 0;
+    let view = VView::new(&bytes, &mut offset).unwrap();
+    assert_eq!(offset, 28);
+    assert_eq!(view.b().unwrap(), 2);
+    assert_eq!(view.a().unwrap(), 1);
+}
+
+#[derive(ZeroFormatter, Default, Debug, PartialEq, Clone)]
+#[zero(target = "Buffer<Cursor<Vec<u8>>>", tracked)]
+pub struct T {
+    #[zero(index = 0)]
+    a: i32,
+    #[zero(index = 1)]
+    b: i64
+}
+
+#[test]
+fn tracked() {
+    let mut c = Buffer::new(Cursor::new(Vec::new()));
+    c.serialize(0, T { a: 1, b: 2 }).unwrap();
+    let mut offset = 0;
+    let mut tracked: Tracked<T> = c.deserialize(&mut offset).unwrap();
+    tracked.b = 3;
+    assert_eq!(c.serialize(0, tracked).unwrap(), 28);
+    let mut offset = 0;
+    assert_eq!(T { a: 1, b: 3 }, c.deserialize(&mut offset).unwrap());
+}